@@ -1,28 +1,70 @@
-use crate::chart::ChartState;
+use crate::bindings::{Command, KeyBindings};
+use crate::chart::{ChartMode, ChartState};
+use crate::config::PanelLayout;
 use crate::cursor::Cursor;
+use crate::histogram::RateState;
+use crate::keys::{extract_keys, KeyExtractor, Keys};
+use crate::spans::{find_spans, Span, SpanRule};
+use crate::stats::Percentiles;
+use crate::templates::{TemplateStats, Templates};
+use crate::theme::Theme;
 use chrono::{DateTime, Duration, NaiveDateTime, Utc};
 use lazycell::LazyCell;
 use lazysort::SortedBy;
 use rayon::prelude::*;
+use std::cmp::Reverse;
+use std::collections::HashMap;
 use std::ops::Range;
+use std::path::PathBuf;
+use tui::layout::Rect;
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum Panel {
     Log,
     Chart,
+    Rate,
+    Distribution,
     List,
+    Spans,
+    Templates,
 }
 
 impl Panel {
     fn next(self) -> Self {
         match self {
             Panel::Log => Panel::Chart,
-            Panel::Chart => Panel::List,
-            Panel::List => Panel::Log,
+            Panel::Chart => Panel::Rate,
+            Panel::Rate => Panel::Distribution,
+            Panel::Distribution => Panel::List,
+            Panel::List => Panel::Spans,
+            Panel::Spans => Panel::Templates,
+            Panel::Templates => Panel::Log,
         }
     }
 }
 
+/// The plotting region of the chart as of the last draw, and the range of x values
+/// it shows. Used to map mouse positions to chart coordinates.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ChartArea {
+    pub plot: Rect,
+    pub x_bounds: (f64, f64),
+}
+
+impl ChartArea {
+    /// The x value at `column`, which is clamped to lie within the plotting region.
+    pub fn x_value(&self, column: u16) -> f64 {
+        let right = self.plot.x + self.plot.width.saturating_sub(1);
+        let column = column.max(self.plot.x).min(right);
+        let position = (column - self.plot.x) as f64 / (right - self.plot.x).max(1) as f64;
+        self.x_bounds.0 + position * (self.x_bounds.1 - self.x_bounds.0)
+    }
+}
+
+fn contains(rect: Rect, column: u16, row: u16) -> bool {
+    column >= rect.x && column < rect.x + rect.width && row >= rect.y && row < rect.y + rect.height
+}
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum Status {
     Active,
@@ -30,31 +72,250 @@ pub enum Status {
 }
 
 // Total hack to deal with "PT" prefix added by Duration::Display. TODO: replace
-fn render_duration(dur: Duration) -> String {
+pub fn render_duration(dur: Duration) -> String {
     format!("{}", dur)[2..].to_string()
 }
 
+/// Renders a duration as seconds with millisecond precision, e.g. "1.500s".
+pub fn render_seconds(dur: Duration) -> String {
+    format!("{:.3}s", dur.num_milliseconds() as f64 / 1000.0)
+}
+
+/// Renders a duration which may be negative as seconds, with an explicit sign.
+pub fn render_seconds_delta(delta: Duration) -> String {
+    format!("{:+.3}s", delta.num_milliseconds() as f64 / 1000.0)
+}
+
+/// Renders a duration which may be negative, with an explicit sign.
+pub fn render_delta(delta: Duration) -> String {
+    if delta < Duration::zero() {
+        format!("-{}", render_duration(-delta))
+    } else {
+        format!("+{}", render_duration(delta))
+    }
+}
+
+/// The timestamp format used by the log panel's timestamp column unless one is provided.
+pub const DEFAULT_TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.3f";
+
+/// Which of the two lines either side of a gap the gap's elapsed time is attributed to.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Attribution {
+    /// The line after the gap, i.e. elapsed times are measured since the previous line
+    #[default]
+    Following,
+    /// The line before the gap, i.e. elapsed times are measured until the next line
+    Preceding,
+}
+
+impl Attribution {
+    fn toggle(self) -> Self {
+        match self {
+            Attribution::Following => Attribution::Preceding,
+            Attribution::Preceding => Attribution::Following,
+        }
+    }
+}
+
+impl std::str::FromStr for Attribution {
+    type Err = failure::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "following" => Ok(Attribution::Following),
+            "preceding" => Ok(Attribution::Preceding),
+            _ => Err(failure::format_err!(
+                "Invalid attribution '{}', expected 'following' or 'preceding'",
+                s
+            )),
+        }
+    }
+}
+
+/// Whether the diffs panel shows the lines with largest or smallest elapsed times.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DiffSelection {
+    Largest,
+    /// Lines with zero elapsed time are excluded as there are typically very many of them
+    SmallestNonZero,
+}
+
+impl DiffSelection {
+    fn toggle(self) -> Self {
+        match self {
+            DiffSelection::Largest => DiffSelection::SmallestNonZero,
+            DiffSelection::SmallestNonZero => DiffSelection::Largest,
+        }
+    }
+}
+
+/// The order of the lines shown in the diffs panel.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DiffOrder {
+    /// Decreasing elapsed time when showing the largest diffs, and increasing otherwise
+    Elapsed,
+    LineNumber,
+    Timestamp,
+}
+
+impl DiffOrder {
+    fn next(self) -> Self {
+        match self {
+            DiffOrder::Elapsed => DiffOrder::LineNumber,
+            DiffOrder::LineNumber => DiffOrder::Timestamp,
+            DiffOrder::Timestamp => DiffOrder::Elapsed,
+        }
+    }
+}
+
+impl std::str::FromStr for DiffOrder {
+    type Err = failure::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "elapsed" => Ok(DiffOrder::Elapsed),
+            "line" => Ok(DiffOrder::LineNumber),
+            "time" => Ok(DiffOrder::Timestamp),
+            _ => Err(failure::format_err!(
+                "Invalid diff order '{}', expected 'elapsed', 'line' or 'time'",
+                s
+            )),
+        }
+    }
+}
+
+/// Controls which lines are shown in the diffs panel, and in what order.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct DiffListOptions {
+    /// The maximum number of lines to show
+    pub count: usize,
+    pub selection: DiffSelection,
+    pub order: DiffOrder,
+}
+
+impl Default for DiffListOptions {
+    fn default() -> Self {
+        DiffListOptions {
+            count: 1000,
+            selection: DiffSelection::Largest,
+            order: DiffOrder::Elapsed,
+        }
+    }
+}
+
+impl DiffListOptions {
+    /// Doubles the number of lines to show, up to `max` lines unless already above it.
+    fn grow(&mut self, max: usize) {
+        let count = self.count.saturating_mul(2).max(1).min(max);
+        self.count = self.count.max(count);
+    }
+
+    /// Halves the number of lines to show, leaving at least one.
+    fn shrink(&mut self) {
+        self.count = (self.count / 2).max(1);
+    }
+}
+
+/// Options controlling how the log is analysed.
+#[derive(Debug, Clone, Default)]
+pub struct Settings {
+    pub span_rules: Vec<SpanRule>,
+    /// If set then elapsed times are computed relative to the
+    /// previous line with the same key
+    pub key_extractor: Option<KeyExtractor>,
+    pub attribution: Attribution,
+    pub diff_list: DiffListOptions,
+    /// The maximum number of entries from the diffs list to mark on the chart
+    pub chart_markers: usize,
+    /// Where to save the chart when exporting it as an SVG image from within the app
+    pub chart_svg_path: PathBuf,
+    /// Where to save lines when exporting them from within the app, as CSV or as JSON
+    /// lines if the extension is "jsonl"
+    pub export_path: PathBuf,
+    /// Lines with elapsed times above this are highlighted in the log panel
+    pub gap_threshold: Option<Duration>,
+    /// A chrono format string for the log panel's timestamp column
+    pub timestamp_format: Option<String>,
+    pub layout: PanelLayout,
+    pub theme: Theme,
+    pub bindings: KeyBindings,
+}
+
+// Elapsed times are computed between consecutive lines with the same key.
+// Lines without a key are treated as sharing a key.
+#[inline(never)]
+fn elapsed_times(
+    timestamps: &[DateTime<Utc>],
+    keys: &[Option<usize>],
+    attribution: Attribution,
+) -> Vec<Duration> {
+    assert_eq!(timestamps.len(), keys.len());
+
+    let mut elapsed = vec![Duration::zero(); timestamps.len()];
+    let mut prev = HashMap::new();
+
+    for i in 0..timestamps.len() {
+        if let Some(p) = prev.insert(keys[i], i) {
+            let diff = timestamps[i] - timestamps[p];
+            match attribution {
+                Attribution::Following => elapsed[i] = diff,
+                Attribution::Preceding => elapsed[p] = diff,
+            }
+        }
+    }
+
+    elapsed
+}
+
 #[inline(never)]
 fn create_annotated_lines<'a>(
     lines: &'a [&'a str],
     timestamps: &[DateTime<Utc>],
+    keys: &[Option<usize>],
+    attribution: Attribution,
 ) -> Vec<AnnotatedLine<'a>> {
     assert_eq!(lines.len(), timestamps.len());
 
+    let elapsed = elapsed_times(timestamps, keys, attribution);
     let mut annotated = Vec::with_capacity(lines.len());
-    let mut prev = timestamps[0];
 
     for i in 0..lines.len() {
-        let line = lines[i];
-        let timestamp = timestamps[i];
-        let diff = timestamp - prev;
-        prev = timestamp;
-        annotated.push(AnnotatedLine::new(i, line, timestamp, diff));
+        annotated.push(AnnotatedLine::new(
+            i,
+            lines[i],
+            timestamps[i],
+            keys[i],
+            elapsed[i],
+        ));
     }
 
     annotated
 }
 
+fn max_elapsed_millis(lines: &[AnnotatedLine]) -> f64 {
+    lines.iter().map(|l| l.elapsed_millis).fold(0.0, f64::max)
+}
+
+fn elapsed_percentiles(lines: &[AnnotatedLine]) -> Percentiles {
+    let mut elapsed: Vec<_> = lines.iter().map(|l| l.elapsed).collect();
+    elapsed.par_sort_unstable();
+    Percentiles::new(&elapsed)
+}
+
+// Elapsed time for each line as a fraction of the total. When elapsed times are
+// computed per key their sum can exceed the time between the first and last lines.
+// If no time elapses at all then every fraction is zero.
+fn chart_deltas(lines: &[AnnotatedLine]) -> Vec<f64> {
+    let total_millis: f64 = lines.iter().map(|l| l.elapsed_millis).sum();
+    if total_millis == 0.0 {
+        return vec![0.0; lines.len()];
+    }
+    lines
+        .iter()
+        .map(|l| l.elapsed_millis / total_millis)
+        .collect()
+}
+
 pub fn extract_timestamp(line: &str) -> Option<DateTime<Utc>> {
     if line.len() >= 24 {
         let p = NaiveDateTime::parse_from_str(&line[0..24], "%Y-%m-%d %H:%M:%S.%3fZ").ok();
@@ -97,6 +358,8 @@ pub struct AnnotatedLine<'a> {
     pub line_number: usize,
     pub line: &'a str,
     pub timestamp: DateTime<Utc>,
+    /// Index into `App::keys`, if a key extractor was provided
+    pub key: Option<usize>,
     pub elapsed: Duration,
     pub elapsed_string: LazyCell<String>,
     pub elapsed_millis: f64,
@@ -107,18 +370,26 @@ impl<'a> AnnotatedLine<'a> {
         line_number: usize,
         line: &'a str,
         timestamp: DateTime<Utc>,
+        key: Option<usize>,
         elapsed: Duration,
     ) -> AnnotatedLine {
         AnnotatedLine {
             line_number,
             line,
             timestamp,
+            key,
             elapsed,
             elapsed_string: LazyCell::new(),
             elapsed_millis: elapsed.num_milliseconds() as f64,
         }
     }
 
+    fn set_elapsed(&mut self, elapsed: Duration) {
+        self.elapsed = elapsed;
+        self.elapsed_string = LazyCell::new();
+        self.elapsed_millis = elapsed.num_milliseconds() as f64;
+    }
+
     pub fn elapsed_string(&self) -> &str {
         if !self.elapsed_string.filled() {
             self.elapsed_string
@@ -132,48 +403,301 @@ impl<'a> AnnotatedLine<'a> {
 #[derive(Debug)]
 pub struct App<'a> {
     pub lines: Vec<AnnotatedLine<'a>>,
-    // The top 1000 lines by decreasing elapsed time
+    pub keys: Keys,
+    pub attribution: Attribution,
+    // The lines with the largest (or smallest) elapsed times, restricted to lines
+    // with key `key_filter` if this is set. See `diff_list` for the size and order.
     pub largest_diffs: Vec<AnnotatedLine<'a>>,
+    pub diff_list: DiffListOptions,
+    pub key_filter: Option<usize>,
+    pub max_elapsed_millis: f64,
+    pub elapsed_percentiles: Percentiles,
+    pub max_line_len: usize,
+    // Spans matching the user-provided rules, by decreasing duration
+    pub spans: Vec<Span>,
+    pub show_spans: bool,
+    pub templates: Templates,
+    // Elapsed times aggregated by template, by decreasing total elapsed time
+    pub template_stats: Vec<TemplateStats>,
     pub log_cursor: Cursor,
     pub diff_cursor: Cursor,
+    pub span_cursor: Cursor,
+    pub template_cursor: Cursor,
     pub active: Panel,
+    // The panels shown in the left and right of the bottom row
+    pub left_panel: Panel,
+    pub right_panel: Panel,
     pub chart_state: ChartState,
+    pub chart_mode: ChartMode,
+    pub chart_markers: usize,
+    pub show_chart_markers: bool,
+    // Seconds since the first line, for each line
+    pub times: Vec<f64>,
+    pub rate_state: RateState,
     pub log_bar_zoom: f64,
     pub help_mode: bool,
+    // Screen areas of the visible panels, as of the last draw
+    pub panel_areas: Vec<(Panel, Rect)>,
+    pub chart_area: Option<ChartArea>,
+    // Start and end columns of an in-progress mouse drag on the chart
+    pub chart_drag: Option<(u16, u16)>,
+    // The line at which a keyboard selection of a range on the chart started
+    pub chart_selection_start: Option<usize>,
+    pub chart_svg_path: PathBuf,
+    pub export_path: PathBuf,
+    // Shown until the next input, e.g. to report the result of saving a file
+    pub status_message: Option<String>,
+    pub gap_threshold: Option<Duration>,
+    // The lines with elapsed times above `gap_threshold`, in increasing order
+    pub gap_lines: Vec<usize>,
+    // Optional columns in the log panel
+    pub show_timestamps: bool,
+    pub show_relative_times: bool,
+    pub timestamp_format: String,
+    // Relative times are measured from this line, or from the first line if not set
+    pub reference_line: Option<usize>,
+    pub layout: PanelLayout,
+    // If true then only the active panel is shown
+    pub maximised: bool,
+    pub theme: Theme,
+    pub bindings: KeyBindings,
+    // Set when quit has been pressed once and needs confirming
+    pub quit_pending: bool,
 }
 
 impl<'a> App<'a> {
-    pub fn new(log: &'a [&'a str]) -> App<'a> {
+    pub fn new(log: &'a [&'a str], settings: &Settings) -> App<'a> {
         let num_lines = log.len();
         let max_len = log.iter().map(|l| l.len()).max().unwrap();
         let timestamps: Vec<_> = log.par_iter().map(|l| extract_timestamp(l)).collect();
         let timestamps = fill_in_timestamps(&timestamps);
-        let lines = create_annotated_lines(&log, &timestamps);
-
-        let largest_diffs: Vec<_> = lines
-            .iter()
-            .sorted_by(|x, y| y.elapsed.cmp(&x.elapsed))
-            .take(1000)
-            .cloned()
-            .collect();
+        let keys = match &settings.key_extractor {
+            Some(extractor) => extract_keys(extractor, log),
+            None => Keys {
+                names: Vec::new(),
+                ids: vec![None; num_lines],
+            },
+        };
+        let lines = create_annotated_lines(log, &timestamps, &keys.ids, settings.attribution);
+        let spans = find_spans(&settings.span_rules, log, &timestamps);
+        let templates = Templates::new(log);
 
-        let total_time = lines[lines.len() - 1].timestamp - lines[0].timestamp;
-        let total_millis = total_time.num_milliseconds() as f64;
-        let deltas = lines
+        let deltas = chart_deltas(&lines);
+        let max_elapsed_millis = max_elapsed_millis(&lines);
+        let elapsed_percentiles = elapsed_percentiles(&lines);
+        let times: Vec<_> = lines
             .iter()
-            .map(|l| l.elapsed_millis / total_millis)
+            .map(|l| (l.timestamp - timestamps[0]).num_milliseconds() as f64 / 1000.0)
             .collect();
+        let extent = (
+            times.iter().cloned().fold(0.0, f64::min),
+            times.iter().cloned().fold(0.0, f64::max),
+        );
 
-        App {
+        let mut app = App {
             lines,
-            largest_diffs,
+            keys,
+            attribution: settings.attribution,
+            largest_diffs: Vec::new(),
+            diff_list: settings.diff_list,
+            key_filter: None,
+            max_elapsed_millis,
+            elapsed_percentiles,
+            max_line_len: max_len,
+            span_cursor: Cursor::new(max_len - 1, spans.len().max(1) - 1),
+            spans,
+            show_spans: !settings.span_rules.is_empty(),
+            templates,
+            template_stats: Vec::new(),
             log_cursor: Cursor::new(max_len - 1, num_lines - 1),
-            diff_cursor: Cursor::new(max_len - 1, num_lines - 1),
+            diff_cursor: Cursor::new(max_len - 1, 0),
+            template_cursor: Cursor::new(max_len - 1, 0),
             active: Panel::Log,
+            left_panel: Panel::Chart,
+            right_panel: Panel::List,
             chart_state: ChartState::new(deltas),
+            chart_mode: ChartMode::LineNumber,
+            chart_markers: settings.chart_markers,
+            show_chart_markers: true,
+            times,
+            rate_state: RateState::new(extent),
             log_bar_zoom: 1.0,
             help_mode: false,
+            panel_areas: Vec::new(),
+            chart_area: None,
+            chart_drag: None,
+            chart_selection_start: None,
+            chart_svg_path: settings.chart_svg_path.clone(),
+            export_path: settings.export_path.clone(),
+            status_message: None,
+            gap_threshold: settings.gap_threshold,
+            gap_lines: Vec::new(),
+            show_timestamps: false,
+            show_relative_times: false,
+            timestamp_format: settings
+                .timestamp_format
+                .clone()
+                .unwrap_or_else(|| DEFAULT_TIMESTAMP_FORMAT.to_string()),
+            reference_line: None,
+            layout: settings.layout,
+            maximised: false,
+            theme: settings.theme,
+            bindings: settings.bindings.clone(),
+            quit_pending: false,
+        };
+        app.update_largest_diffs();
+        app.update_template_stats();
+        app.update_gap_lines();
+        app
+    }
+
+    // Recomputes elapsed times and everything derived from them
+    fn toggle_attribution(&mut self) {
+        self.attribution = self.attribution.toggle();
+
+        let timestamps: Vec<_> = self.lines.iter().map(|l| l.timestamp).collect();
+        let elapsed = elapsed_times(&timestamps, &self.keys.ids, self.attribution);
+        for (line, e) in self.lines.iter_mut().zip(elapsed) {
+            line.set_elapsed(e);
+        }
+
+        self.max_elapsed_millis = max_elapsed_millis(&self.lines);
+        self.elapsed_percentiles = elapsed_percentiles(&self.lines);
+        self.chart_state.set_deltas(chart_deltas(&self.lines));
+        self.update_largest_diffs();
+        self.update_template_stats();
+        self.update_gap_lines();
+    }
+
+    fn update_gap_lines(&mut self) {
+        self.gap_lines = match self.gap_threshold {
+            Some(threshold) => self
+                .lines
+                .iter()
+                .filter(|l| l.elapsed > threshold)
+                .map(|l| l.line_number)
+                .collect(),
+            None => Vec::new(),
+        };
+    }
+
+    pub fn timestamp_string(&self, line: usize) -> String {
+        self.lines[line]
+            .timestamp
+            .format(&self.timestamp_format)
+            .to_string()
+    }
+
+    /// The time since the reference line, or since the first line if there isn't one.
+    pub fn relative_time_string(&self, line: usize) -> String {
+        let reference = self.reference_line.unwrap_or(0);
+        render_delta(self.lines[line].timestamp - self.lines[reference].timestamp)
+    }
+
+    // Makes the current line the reference line for relative times, or clears the reference
+    // line if it is already the current line
+    fn toggle_reference_line(&mut self) {
+        let current = self.log_cursor.y;
+        self.reference_line = if self.reference_line == Some(current) {
+            None
+        } else {
+            Some(current)
+        };
+        self.show_relative_times = true;
+    }
+
+    pub fn is_gap(&self, line: usize) -> bool {
+        self.gap_lines.binary_search(&line).is_ok()
+    }
+
+    fn update_template_stats(&mut self) {
+        let elapsed: Vec<_> = self.lines.iter().map(|l| l.elapsed).collect();
+        self.template_stats = self.templates.stats(&elapsed);
+        let max_template_len = self.templates.names.iter().map(|t| t.len()).max().unwrap();
+        self.template_cursor = Cursor::new(max_template_len, self.template_stats.len() - 1);
+    }
+
+    fn update_largest_diffs(&mut self) {
+        let key_filter = self.key_filter;
+        let DiffListOptions {
+            count,
+            selection,
+            order,
+        } = self.diff_list;
+
+        let candidates = self
+            .lines
+            .iter()
+            .filter(|l| key_filter.is_none() || l.key == key_filter);
+
+        let mut largest_diffs: Vec<_> = match selection {
+            DiffSelection::Largest => candidates
+                .sorted_by(|x, y| y.elapsed.cmp(&x.elapsed))
+                .take(count)
+                .cloned()
+                .collect(),
+            DiffSelection::SmallestNonZero => candidates
+                .filter(|l| l.elapsed > Duration::zero())
+                .sorted_by(|x, y| x.elapsed.cmp(&y.elapsed))
+                .take(count)
+                .cloned()
+                .collect(),
+        };
+
+        match order {
+            DiffOrder::Elapsed => {}
+            DiffOrder::LineNumber => largest_diffs.sort_by_key(|l| l.line_number),
+            DiffOrder::Timestamp => largest_diffs.sort_by_key(|l| (l.timestamp, l.line_number)),
+        }
+
+        self.largest_diffs = largest_diffs;
+        self.diff_cursor = Cursor::new(self.max_line_len - 1, self.largest_diffs.len().max(1) - 1);
+    }
+
+    /// Line numbers of the first `chart_markers` entries of the diffs list which lie within
+    /// the visible chart interval, taking entries in decreasing order of elapsed time when
+    /// showing the largest diffs and increasing order otherwise.
+    pub fn chart_marker_lines(&self) -> Vec<usize> {
+        let (lower, upper) = self.chart_state.interval;
+        let mut visible: Vec<_> = self
+            .largest_diffs
+            .iter()
+            .filter(|l| l.line_number >= lower && l.line_number < upper)
+            .collect();
+        match self.diff_list.selection {
+            DiffSelection::Largest => visible.sort_by_key(|l| Reverse(l.elapsed)),
+            DiffSelection::SmallestNonZero => visible.sort_by_key(|l| l.elapsed),
+        }
+        visible
+            .iter()
+            .take(self.chart_markers)
+            .map(|l| l.line_number)
+            .collect()
+    }
+
+    /// The line currently selected in the diffs list, if any.
+    pub fn selected_diff_line(&self) -> Option<usize> {
+        self.largest_diffs
+            .get(self.diff_cursor.y)
+            .map(|l| l.line_number)
+    }
+
+    pub fn key_name(&self, key: Option<usize>) -> Option<&str> {
+        key.map(|k| self.keys.names[k].as_str())
+    }
+
+    // Cycles through showing largest diffs for all lines, then for each key in turn
+    fn cycle_key_filter(&mut self) {
+        let num_keys = self.keys.names.len();
+        if num_keys == 0 {
+            return;
         }
+        self.key_filter = match self.key_filter {
+            None => Some(0),
+            Some(k) if k + 1 < num_keys => Some(k + 1),
+            Some(_) => None,
+        };
+        self.update_largest_diffs();
     }
 
     pub fn vertical_log_scroll(&self) -> usize {
@@ -192,12 +716,28 @@ impl<'a> App<'a> {
         self.diff_cursor.x
     }
 
+    pub fn vertical_template_scroll(&self) -> usize {
+        self.template_cursor.y
+    }
+
+    pub fn horizontal_template_scroll(&self) -> usize {
+        self.template_cursor.x
+    }
+
+    pub fn vertical_span_scroll(&self) -> usize {
+        self.span_cursor.y
+    }
+
+    pub fn horizontal_span_scroll(&self) -> usize {
+        self.span_cursor.x
+    }
+
     pub fn lines_per_pixel(&self) -> usize {
-        self.chart_state.interval_length() / self.chart_state.horizontal_resolution
+        self.chart_state.step_size()
     }
 
     pub fn elapsed_time_ratios(&self, from: usize, to: usize) -> Vec<f64> {
-        let max_diff = self.largest_diffs[0].elapsed_millis;
+        let max_diff = self.max_elapsed_millis;
         self.lines
             .iter()
             .skip(from)
@@ -210,16 +750,80 @@ impl<'a> App<'a> {
         (0..self.lines.len())
     }
 
+    pub fn current_time(&self) -> f64 {
+        self.times[self.log_cursor.y]
+    }
+
+    fn on_current_line_changed(&mut self) {
+        self.chart_state.update(self.log_cursor.y);
+        self.rate_state.update(self.current_time());
+    }
+
     fn scroll_log(&mut self, n: isize) {
         self.log_cursor.move_y(n);
-        self.chart_state.update(self.log_cursor.y);
+        self.on_current_line_changed();
+    }
+
+    // Moves to the first line at least `seconds` after the current line, or the last line at
+    // least `-seconds` before it, and always by at least one line. Assumes times are increasing.
+    fn scroll_log_by_time(&mut self, seconds: f64) {
+        let current = self.log_cursor.y as isize;
+        let target = self.current_time() + seconds;
+        let target_line = match self
+            .times
+            .binary_search_by(|t| t.partial_cmp(&target).unwrap())
+        {
+            Ok(i) => i as isize,
+            Err(i) if seconds > 0.0 => i as isize,
+            Err(i) => i as isize - 1,
+        };
+        if seconds > 0.0 {
+            self.scroll_log((target_line - current).max(1));
+        } else {
+            self.scroll_log((target_line - current).min(-1));
+        }
+    }
+
+    // Leaves the previous line visible above the target line
+    fn jump_to_line(&mut self, target_line: usize) {
+        self.log_cursor.y = if target_line == 0 { 0 } else { target_line - 1 };
+        self.on_current_line_changed();
+    }
+
+    // Jumps to the next line above the gap threshold after the current position, or the
+    // previous one before it, in the same way as selecting a line in the other panels
+    fn jump_to_gap(&mut self, forwards: bool) {
+        let position = |line: usize| line.saturating_sub(1);
+        let current = self.log_cursor.y;
+        let target = if forwards {
+            self.gap_lines.iter().find(|l| position(**l) > current)
+        } else {
+            self.gap_lines
+                .iter()
+                .rev()
+                .find(|l| position(**l) < current)
+        };
+        match target {
+            Some(line) => self.jump_to_line(*line),
+            None if self.gap_threshold.is_some() => {
+                self.status_message = Some(format!(
+                    "No {} line with a gap above the threshold",
+                    if forwards { "later" } else { "earlier" }
+                ))
+            }
+            None => self.status_message = Some("No gap threshold set, use --gap".to_string()),
+        }
     }
 
     pub fn on_up(&mut self) {
         match self.active {
             Panel::Log => self.scroll_log(-1),
             Panel::Chart => self.chart_state.zoom_in(self.log_cursor.y),
+            Panel::Rate => self.rate_state.zoom_in(self.current_time()),
+            Panel::Distribution => {}
             Panel::List => self.diff_cursor.move_y(-1),
+            Panel::Spans => self.span_cursor.move_y(-1),
+            Panel::Templates => self.template_cursor.move_y(-1),
         }
     }
 
@@ -227,7 +831,11 @@ impl<'a> App<'a> {
         match self.active {
             Panel::Log => self.scroll_log(1),
             Panel::Chart => self.chart_state.zoom_out(self.log_cursor.y),
+            Panel::Rate => self.rate_state.zoom_out(self.current_time()),
+            Panel::Distribution => {}
             Panel::List => self.diff_cursor.move_y(1),
+            Panel::Spans => self.span_cursor.move_y(1),
+            Panel::Templates => self.template_cursor.move_y(1),
         }
     }
 
@@ -239,7 +847,15 @@ impl<'a> App<'a> {
                     self.chart_state.zoom_in(self.log_cursor.y);
                 }
             }
+            Panel::Rate => {
+                for _ in 0..3 {
+                    self.rate_state.zoom_in(self.current_time());
+                }
+            }
+            Panel::Distribution => {}
             Panel::List => self.diff_cursor.move_y(-15),
+            Panel::Spans => self.span_cursor.move_y(-15),
+            Panel::Templates => self.template_cursor.move_y(-15),
         }
     }
 
@@ -251,7 +867,15 @@ impl<'a> App<'a> {
                     self.chart_state.zoom_out(self.log_cursor.y);
                 }
             }
+            Panel::Rate => {
+                for _ in 0..3 {
+                    self.rate_state.zoom_out(self.current_time());
+                }
+            }
+            Panel::Distribution => {}
             Panel::List => self.diff_cursor.move_y(15),
+            Panel::Spans => self.span_cursor.move_y(15),
+            Panel::Templates => self.template_cursor.move_y(15),
         }
     }
 
@@ -259,7 +883,11 @@ impl<'a> App<'a> {
         match self.active {
             Panel::Log => self.log_cursor.move_x(3),
             Panel::Chart => self.scroll_log(self.lines_per_pixel() as isize),
+            Panel::Rate => self.scroll_log_by_time(self.rate_state.bucket_width()),
+            Panel::Distribution => {}
             Panel::List => self.diff_cursor.move_x(3),
+            Panel::Spans => self.span_cursor.move_x(3),
+            Panel::Templates => self.template_cursor.move_x(3),
         }
     }
 
@@ -267,7 +895,11 @@ impl<'a> App<'a> {
         match self.active {
             Panel::Log => self.log_cursor.move_x(-3),
             Panel::Chart => self.scroll_log(-1 * self.lines_per_pixel() as isize),
+            Panel::Rate => self.scroll_log_by_time(-self.rate_state.bucket_width()),
+            Panel::Distribution => {}
             Panel::List => self.diff_cursor.move_x(-3),
+            Panel::Spans => self.span_cursor.move_x(-3),
+            Panel::Templates => self.template_cursor.move_x(-3),
         }
     }
 
@@ -275,7 +907,11 @@ impl<'a> App<'a> {
         match self.active {
             Panel::Log => self.log_cursor.move_to_left_boundary(),
             Panel::Chart => self.scroll_log(-15 * self.lines_per_pixel() as isize),
+            Panel::Rate => self.scroll_log_by_time(-15.0 * self.rate_state.bucket_width()),
+            Panel::Distribution => {}
             Panel::List => self.diff_cursor.move_to_left_boundary(),
+            Panel::Spans => self.span_cursor.move_to_left_boundary(),
+            Panel::Templates => self.template_cursor.move_to_left_boundary(),
         }
     }
 
@@ -283,45 +919,288 @@ impl<'a> App<'a> {
         match self.active {
             Panel::Log => self.log_cursor.move_to_right_boundary(),
             Panel::Chart => self.scroll_log(15 * self.lines_per_pixel() as isize),
+            Panel::Rate => self.scroll_log_by_time(15.0 * self.rate_state.bucket_width()),
+            Panel::Distribution => {}
             Panel::List => self.diff_cursor.move_to_right_boundary(),
+            Panel::Spans => self.span_cursor.move_to_right_boundary(),
+            Panel::Templates => self.template_cursor.move_to_right_boundary(),
+        }
+    }
+
+    pub fn on_top(&mut self) {
+        match self.active {
+            Panel::List => self.diff_cursor.y = 0,
+            Panel::Spans => self.span_cursor.y = 0,
+            Panel::Templates => self.template_cursor.y = 0,
+            _ => {
+                self.log_cursor.y = 0;
+                self.on_current_line_changed();
+            }
+        }
+    }
+
+    pub fn on_bottom(&mut self) {
+        match self.active {
+            Panel::List => self.diff_cursor.y = self.diff_cursor.max_y,
+            Panel::Spans => self.span_cursor.y = self.span_cursor.max_y,
+            Panel::Templates => self.template_cursor.y = self.template_cursor.max_y,
+            _ => {
+                self.log_cursor.y = self.log_cursor.max_y;
+                self.on_current_line_changed();
+            }
         }
     }
 
     pub fn on_tab(&mut self) {
-        self.active = self.active.next();
+        loop {
+            self.active = self.active.next();
+            let skipped = self.active == Panel::Spans && !self.show_spans;
+            if !skipped && self.is_visible(self.active) {
+                break;
+            }
+        }
+        match self.active {
+            Panel::Chart | Panel::Rate | Panel::Distribution => self.left_panel = self.active,
+            Panel::List | Panel::Spans | Panel::Templates => self.right_panel = self.active,
+            Panel::Log => {}
+        }
+    }
+
+    /// Whether `panel` is in a part of the layout which hasn't been hidden.
+    pub fn is_visible(&self, panel: Panel) -> bool {
+        match panel {
+            Panel::Log => !self.layout.hide_log,
+            Panel::Chart | Panel::Rate | Panel::Distribution => !self.layout.hide_left,
+            Panel::List | Panel::Spans | Panel::Templates => !self.layout.hide_right,
+        }
+    }
+
+    // Hides the active panel and moves to the next visible one, unless it is the only one
+    fn hide_active_panel(&mut self) {
+        let mut layout = self.layout;
+        match self.active {
+            Panel::Log => layout.hide_log = true,
+            Panel::Chart | Panel::Rate | Panel::Distribution => layout.hide_left = true,
+            Panel::List | Panel::Spans | Panel::Templates => layout.hide_right = true,
+        }
+        if layout.hide_log && !layout.show_bottom_row() {
+            self.status_message = Some("The last visible panel can't be hidden".to_string());
+            return;
+        }
+        self.layout = layout;
+        self.maximised = false;
+        self.on_tab();
+    }
+
+    fn show_all_panels(&mut self) {
+        self.layout.hide_log = false;
+        self.layout.hide_left = false;
+        self.layout.hide_right = false;
     }
 
     pub fn on_enter(&mut self) {
-        if self.active == Panel::List {
-            let selected_line = self.diff_cursor.y;
-            let target_line = self.largest_diffs[selected_line].line_number;
-            self.log_cursor.y = if target_line == 0 { 0 } else { target_line - 1 };
+        match self.active {
+            Panel::List => {
+                if let Some(target_line) = self.selected_diff_line() {
+                    self.jump_to_line(target_line);
+                }
+            }
+            Panel::Spans => {
+                if let Some(span) = self.spans.get(self.span_cursor.y) {
+                    self.jump_to_line(span.start);
+                }
+            }
+            Panel::Templates => {
+                if let Some(stats) = self.template_stats.get(self.template_cursor.y) {
+                    self.jump_to_line(stats.max_line);
+                }
+            }
+            _ => {}
         }
     }
 
     pub fn on_escape(&mut self) {
         match self.active {
             Panel::Log => self.log_bar_zoom = 1.0,
-            Panel::Chart => self.chart_state.reset_zoom(),
-            Panel::List => {}
+            Panel::Chart => {
+                if self.chart_selection_start.is_some() {
+                    self.chart_selection_start = None;
+                } else {
+                    self.chart_state.reset_zoom();
+                }
+            }
+            Panel::Rate => self.rate_state.reset_zoom(),
+            Panel::Distribution | Panel::List | Panel::Spans | Panel::Templates => {}
+        }
+    }
+
+    pub fn on_backspace(&mut self) {
+        if self.active == Panel::Chart {
+            self.chart_state.zoom_back();
         }
     }
 
-    pub fn on_char(&mut self, c: char) {
-        if c == 'h' {
-            self.help_mode = !self.help_mode;
+    /// Carries out `command`, which must be one that can be used in the active panel.
+    /// Quitting and exporting the chart are handled by the caller.
+    pub fn on_command(&mut self, command: Command) {
+        match command {
+            Command::ToggleHelp => self.help_mode = !self.help_mode,
+            Command::NextPanel => self.on_tab(),
+            Command::Up => self.on_up(),
+            Command::Down => self.on_down(),
+            Command::PageUp => self.on_page_up(),
+            Command::PageDown => self.on_page_down(),
+            Command::Left => self.on_left(),
+            Command::Right => self.on_right(),
+            Command::Home => self.on_home(),
+            Command::End => self.on_end(),
+            Command::Top => self.on_top(),
+            Command::Bottom => self.on_bottom(),
+            Command::Select => self.on_enter(),
+            Command::Reset => self.on_escape(),
+            Command::Back => self.on_backspace(),
+            Command::ToggleAttribution => self.toggle_attribution(),
+            Command::NextGap => self.jump_to_gap(true),
+            Command::PreviousGap => self.jump_to_gap(false),
+            Command::ShrinkLog => self.layout.resize_log(-5),
+            Command::GrowLog => self.layout.resize_log(5),
+            Command::ShrinkLeft => self.layout.resize_left(-5),
+            Command::GrowLeft => self.layout.resize_left(5),
+            Command::Maximise => self.maximised = !self.maximised,
+            Command::HidePanel => self.hide_active_panel(),
+            Command::ShowPanels => self.show_all_panels(),
+            Command::ToggleTimestamps => self.show_timestamps = !self.show_timestamps,
+            Command::ToggleRelativeTimes => self.show_relative_times = !self.show_relative_times,
+            Command::SetReference => self.toggle_reference_line(),
+            Command::Grow | Command::Shrink => self.resize_active(command == Command::Grow),
+            Command::ToggleChartMode => self.chart_mode = self.chart_mode.toggle(),
+            Command::ToggleMarkers => self.show_chart_markers = !self.show_chart_markers,
+            Command::SelectRange => {
+                if self.chart_selection_start.is_some() {
+                    self.zoom_to_chart_selection();
+                } else {
+                    self.chart_selection_start = Some(self.log_cursor.y);
+                }
+            }
+            Command::CycleKeyFilter => self.cycle_key_filter(),
+            Command::CycleOrder => {
+                self.diff_list.order = self.diff_list.order.next();
+                self.update_largest_diffs();
+            }
+            Command::ToggleSmallest => {
+                self.diff_list.selection = self.diff_list.selection.toggle();
+                self.update_largest_diffs();
+            }
+            Command::Quit | Command::ExportChart | Command::ExportLines => {}
         }
-        // +/-
-        if self.active == Panel::Log {
-            if c == '+' {
-                self.log_bar_zoom = 1000.0f64.min(self.log_bar_zoom * 1.5);
+    }
+
+    // Stretches or shrinks the log bars, or doubles or halves the length of the diffs list
+    fn resize_active(&mut self, grow: bool) {
+        match self.active {
+            Panel::Log if grow => self.log_bar_zoom = 1000.0f64.min(self.log_bar_zoom * 1.5),
+            Panel::Log => self.log_bar_zoom = 1.0f64.max(self.log_bar_zoom / 1.5),
+            Panel::List => {
+                if grow {
+                    self.diff_list.grow(self.lines.len());
+                } else {
+                    self.diff_list.shrink();
+                }
+                self.update_largest_diffs();
             }
-            if c == '-' {
-                self.log_bar_zoom = 1.0f64.max(self.log_bar_zoom / 1.5);
+            _ => {}
+        }
+    }
+
+    fn panel_at(&self, column: u16, row: u16) -> Option<Panel> {
+        self.panel_areas
+            .iter()
+            .find(|(_, rect)| contains(*rect, column, row))
+            .map(|(panel, _)| *panel)
+    }
+
+    // The line plotted closest to `column` on the chart
+    fn chart_line_at(&self, column: u16) -> Option<usize> {
+        let x = self.chart_area?.x_value(column);
+        let line = match self.chart_mode {
+            ChartMode::LineNumber => x.round().max(0.0) as usize,
+            ChartMode::Time => match self.times.binary_search_by(|t| t.partial_cmp(&x).unwrap()) {
+                Ok(i) | Err(i) => i,
+            },
+        };
+        Some(line.min(self.lines.len() - 1))
+    }
+
+    /// The inclusive range of lines currently being selected on the chart, either using
+    /// the keyboard or by dragging with the mouse.
+    pub fn chart_selection(&self) -> Option<(usize, usize)> {
+        let (start, end) = match (self.chart_drag, self.chart_selection_start) {
+            (Some((start, end)), _) => (self.chart_line_at(start)?, self.chart_line_at(end)?),
+            (None, Some(start)) => (start, self.log_cursor.y),
+            (None, None) => return None,
+        };
+        Some((start.min(end), start.max(end)))
+    }
+
+    // Zooms the chart to the current selection and ends the selection
+    fn zoom_to_chart_selection(&mut self) {
+        if let Some((start, end)) = self.chart_selection() {
+            self.chart_state.zoom_to(start, end + 1);
+            self.on_current_line_changed();
+        }
+        self.chart_drag = None;
+        self.chart_selection_start = None;
+    }
+
+    /// Activates the panel under the mouse. Clicking on the chart also moves the
+    /// current line and starts a drag, which zooms to the dragged range on release.
+    pub fn on_click(&mut self, column: u16, row: u16) {
+        let panel = match self.panel_at(column, row) {
+            Some(panel) => panel,
+            None => return,
+        };
+        self.active = panel;
+
+        if panel == Panel::Chart {
+            if let Some(line) = self.chart_line_at(column) {
+                self.log_cursor.y = line;
+                self.on_current_line_changed();
+                self.chart_drag = Some((column, column));
             }
         }
     }
 
+    pub fn on_drag(&mut self, column: u16, _row: u16) {
+        if let Some((start, _)) = self.chart_drag {
+            self.chart_drag = Some((start, column));
+        }
+    }
+
+    pub fn on_release(&mut self, column: u16, _row: u16) {
+        let start = match self.chart_drag {
+            Some((start, _)) => start,
+            None => return,
+        };
+        // Ignore small movements during a click
+        if (column as isize - start as isize).abs() < 2 {
+            self.chart_drag = None;
+            return;
+        }
+        self.chart_drag = Some((start, column));
+        self.zoom_to_chart_selection();
+    }
+
+    /// Scrolls the log or list panel under the mouse, without changing the active panel.
+    pub fn on_scroll(&mut self, column: u16, row: u16, delta: isize) {
+        match self.panel_at(column, row) {
+            Some(Panel::Log) => self.scroll_log(delta),
+            Some(Panel::List) => self.diff_cursor.move_y(delta),
+            Some(Panel::Spans) => self.span_cursor.move_y(delta),
+            Some(Panel::Templates) => self.template_cursor.move_y(delta),
+            _ => {}
+        }
+    }
+
     pub fn status(&self, panel: Panel) -> Status {
         if panel == self.active {
             Status::Active
@@ -330,3 +1209,172 @@ impl<'a> App<'a> {
         }
     }
 }
+
+// Also provides fixtures for the tests of other modules
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    /// Timestamps the given numbers of seconds after the Unix epoch.
+    pub fn timestamps(seconds: &[i64]) -> Vec<DateTime<Utc>> {
+        seconds
+            .iter()
+            .map(|s| Utc.timestamp_opt(*s, 0).unwrap())
+            .collect()
+    }
+
+    /// Log lines with the given texts, timestamped the given numbers of milliseconds after
+    /// 2020-02-29 10:00:00. The lines are leaked, which is harmless in a test, so that
+    /// they can be borrowed by an `App` in the same way as string literals.
+    pub fn log_lines(lines: &[(i64, &str)]) -> Vec<&'static str> {
+        let start: DateTime<Utc> = "2020-02-29T10:00:00Z".parse().unwrap();
+        lines
+            .iter()
+            .map(|(millis, text)| {
+                let timestamp = start + Duration::milliseconds(*millis);
+                let line = format!("{} {}", timestamp.format("%Y-%m-%d %H:%M:%S%.3fZ"), text);
+                &*Box::leak(line.into_boxed_str())
+            })
+            .collect()
+    }
+
+    fn seconds(elapsed: &[Duration]) -> Vec<i64> {
+        elapsed.iter().map(|e| e.num_seconds()).collect()
+    }
+
+    #[test]
+    fn test_elapsed_times_by_key() {
+        // Lines for keys 0 and 1 are interleaved, and lines without a key share a key
+        let timestamps = timestamps(&[0, 1, 3, 6, 10, 15]);
+        let keys = [Some(0), Some(1), Some(0), None, Some(1), None];
+        assert_eq!(
+            seconds(&elapsed_times(&timestamps, &keys, Attribution::Following)),
+            vec![0, 0, 3, 0, 9, 9]
+        );
+    }
+
+    #[test]
+    fn test_elapsed_times_attribution() {
+        let timestamps = timestamps(&[0, 1, 3, 6]);
+        let keys = [None; 4];
+
+        // The first line has no previous line
+        assert_eq!(
+            seconds(&elapsed_times(&timestamps, &keys, Attribution::Following)),
+            vec![0, 1, 2, 3]
+        );
+        // The last line has no next line
+        assert_eq!(
+            seconds(&elapsed_times(&timestamps, &keys, Attribution::Preceding)),
+            vec![1, 2, 3, 0]
+        );
+
+        // With keys, the last line for each key has no next line
+        let keys = [Some(0), Some(1), Some(0), Some(1)];
+        assert_eq!(
+            seconds(&elapsed_times(&timestamps, &keys, Attribution::Preceding)),
+            vec![3, 5, 0, 0]
+        );
+    }
+
+    #[test]
+    fn test_chart_deltas_without_elapsed_time() {
+        let lines = log_lines(&[(0, "a"), (0, "b"), (0, "c")]);
+        let app = App::new(&lines, &Settings::default());
+        assert_eq!(app.chart_state.deltas, vec![0.0; 3]);
+
+        let lines = log_lines(&[(0, "a"), (1000, "b"), (4000, "c")]);
+        let app = App::new(&lines, &Settings::default());
+        assert_eq!(app.chart_state.deltas, vec![0.0, 0.25, 0.75]);
+    }
+
+    #[test]
+    fn test_jump_to_gap() {
+        let lines = log_lines(&[
+            (0, "a"),
+            (1000, "b"),
+            (4000, "c"),
+            (5000, "d"),
+            (9000, "e"),
+            (10000, "f"),
+        ]);
+        let settings = Settings {
+            gap_threshold: Some(Duration::seconds(2)),
+            ..Settings::default()
+        };
+        let mut app = App::new(&lines, &settings);
+        assert_eq!(app.gap_lines, vec![2, 4]);
+        assert!(app.is_gap(2) && !app.is_gap(3));
+
+        // Jumps position the cursor in the same way as selecting a line in another panel
+        app.jump_to_gap(true);
+        assert_eq!(app.log_cursor.y, 1);
+        app.jump_to_gap(true);
+        assert_eq!(app.log_cursor.y, 3);
+
+        // There's no wrapping around at either end
+        app.jump_to_gap(true);
+        assert_eq!(app.log_cursor.y, 3);
+        assert!(app.status_message.is_some());
+        app.jump_to_gap(false);
+        assert_eq!(app.log_cursor.y, 1);
+        app.status_message = None;
+        app.jump_to_gap(false);
+        assert_eq!(app.log_cursor.y, 1);
+        assert!(app.status_message.is_some());
+    }
+
+    #[test]
+    fn test_relative_times() {
+        // Timestamps needn't increase, so relative times can be negative
+        let lines = log_lines(&[(1000, "a"), (0, "b"), (3500, "c")]);
+        let mut app = App::new(&lines, &Settings::default());
+        let relative_times = |app: &App| {
+            (0..3)
+                .map(|l| app.relative_time_string(l))
+                .collect::<Vec<_>>()
+        };
+        let deltas = |millis: &[i64]| {
+            millis
+                .iter()
+                .map(|m| render_delta(Duration::milliseconds(*m)))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(relative_times(&app), deltas(&[0, -1000, 2500]));
+
+        app.log_cursor.y = 2;
+        app.toggle_reference_line();
+        assert!(app.show_relative_times);
+        assert_eq!(app.reference_line, Some(2));
+        assert_eq!(relative_times(&app), deltas(&[-2500, -3500, 0]));
+
+        app.toggle_reference_line();
+        assert_eq!(app.reference_line, None);
+        assert_eq!(relative_times(&app), deltas(&[0, -1000, 2500]));
+    }
+
+    #[test]
+    fn test_resize_diff_list() {
+        let mut options = DiffListOptions {
+            count: 3,
+            ..DiffListOptions::default()
+        };
+        options.shrink();
+        assert_eq!(options.count, 1);
+        options.shrink();
+        assert_eq!(options.count, 1);
+        options.grow(5);
+        assert_eq!(options.count, 2);
+        options.grow(5);
+        options.grow(5);
+        assert_eq!(options.count, 5);
+
+        // Growing never reduces the count, and doesn't overflow
+        options.count = usize::MAX - 1;
+        options.grow(5);
+        assert_eq!(options.count, usize::MAX - 1);
+        options.grow(usize::MAX);
+        assert_eq!(options.count, usize::MAX);
+    }
+}
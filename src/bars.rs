@@ -0,0 +1,69 @@
+use tui::buffer::Buffer;
+use tui::layout::Rect;
+use tui::style::Style;
+use tui::widgets::Widget;
+
+// Eighths of a cell, from empty to full
+const BAR_SYMBOLS: [&str; 9] = [" ", "▁", "▂", "▃", "▄", "▅", "▆", "▇", "█"];
+
+/// A vertical bar chart with one single-column bar per value, where
+/// individual columns can be drawn with a different style.
+pub struct Bars {
+    /// Style for columns without a highlight
+    style: Style,
+    /// Bar heights as a proportion of the available height
+    ratios: Vec<f64>,
+    /// Columns to draw using a different style, e.g. to mark empty buckets
+    highlights: Vec<(usize, Style)>,
+}
+
+impl Bars {
+    pub fn new(ratios: Vec<f64>, style: Style) -> Bars {
+        Bars {
+            style,
+            ratios,
+            highlights: Vec::new(),
+        }
+    }
+
+    /// Later highlights take precedence over earlier ones for the same column.
+    pub fn highlight(mut self, column: usize, style: Style) -> Bars {
+        self.highlights.push((column, style));
+        self
+    }
+}
+
+impl Widget for Bars {
+    fn draw(&mut self, area: Rect, buf: &mut Buffer) {
+        if area.height < 1 {
+            return;
+        }
+
+        self.background(area, buf, self.style.bg);
+
+        for (x, ratio) in self.ratios.iter().enumerate() {
+            if x >= area.width as usize {
+                break;
+            }
+
+            let style = self
+                .highlights
+                .iter()
+                .rev()
+                .find(|(column, _)| *column == x)
+                .map(|(_, style)| *style)
+                .unwrap_or(self.style);
+
+            // Bar height in eighths of a cell
+            let mut height = (ratio.clamp(0.0, 1.0) * area.height as f64 * 8.0) as usize;
+
+            for y in (0..area.height).rev() {
+                let symbol = BAR_SYMBOLS[height.min(8)];
+                height = height.saturating_sub(8);
+                buf.get_mut(area.left() + x as u16, area.top() + y)
+                    .set_symbol(symbol)
+                    .set_style(style);
+            }
+        }
+    }
+}
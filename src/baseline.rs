@@ -0,0 +1,199 @@
+//! Stores per-template elapsed time statistics from a known-good log, and checks other
+//! logs against them so that slowdowns can fail a CI job
+
+use crate::app::{render_seconds, App};
+use chrono::Duration;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Write;
+
+/// Elapsed time statistics for the lines matching one template, in milliseconds.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TemplateProfile {
+    pub template: String,
+    pub count: usize,
+    pub total_ms: i64,
+    pub p50_ms: i64,
+    pub p99_ms: i64,
+    pub max_ms: i64,
+}
+
+/// The elapsed time profile of a log, by decreasing total elapsed time per template.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Baseline {
+    pub templates: Vec<TemplateProfile>,
+}
+
+impl Baseline {
+    pub fn new(app: &App) -> Baseline {
+        let templates = app
+            .template_stats
+            .iter()
+            .map(|s| TemplateProfile {
+                template: app.templates.names[s.id].clone(),
+                count: s.count,
+                total_ms: s.total.num_milliseconds(),
+                p50_ms: s.p50.num_milliseconds(),
+                p99_ms: s.p99.num_milliseconds(),
+                max_ms: s.max.num_milliseconds(),
+            })
+            .collect();
+        Baseline { templates }
+    }
+}
+
+/// How much slower a template can get before it counts as a regression. Both limits
+/// must be exceeded, so that small absolute changes in fast templates are ignored.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Thresholds {
+    /// Allowed increase as a percentage of the baseline value
+    pub percent: f64,
+    /// Allowed absolute increase
+    pub min_increase: Duration,
+}
+
+/// A template whose total or p99 elapsed time exceeded the thresholds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Regression {
+    pub template: String,
+    /// "total" or "p99"
+    pub metric: &'static str,
+    pub baseline: Duration,
+    pub current: Duration,
+}
+
+/// Compares the total and p99 elapsed time of each template in `current` with `baseline`.
+/// Templates missing from the baseline are compared against zero, and templates missing
+/// from `current` are ignored. Regressions are sorted by decreasing increase.
+pub fn check(baseline: &Baseline, current: &Baseline, thresholds: Thresholds) -> Vec<Regression> {
+    let previous: HashMap<_, _> = baseline
+        .templates
+        .iter()
+        .map(|t| (t.template.as_str(), t))
+        .collect();
+
+    let mut regressions = Vec::new();
+    for profile in &current.templates {
+        let old = previous.get(profile.template.as_str());
+        let metrics = [
+            ("total", old.map_or(0, |o| o.total_ms), profile.total_ms),
+            ("p99", old.map_or(0, |o| o.p99_ms), profile.p99_ms),
+        ];
+        for (metric, old_ms, new_ms) in metrics.iter().cloned() {
+            let increase = new_ms - old_ms;
+            let allowed = (old_ms as f64 * thresholds.percent / 100.0)
+                .max(thresholds.min_increase.num_milliseconds() as f64);
+            if increase as f64 > allowed {
+                regressions.push(Regression {
+                    template: profile.template.clone(),
+                    metric,
+                    baseline: Duration::milliseconds(old_ms),
+                    current: Duration::milliseconds(new_ms),
+                });
+            }
+        }
+    }
+
+    regressions.sort_by(|x, y| {
+        (y.current - y.baseline)
+            .cmp(&(x.current - x.baseline))
+            .then(x.template.cmp(&y.template))
+    });
+    regressions
+}
+
+/// Writes one row per regression, or a message saying that there were none.
+pub fn write_regressions<W: Write + ?Sized>(
+    regressions: &[Regression],
+    out: &mut W,
+) -> Result<(), failure::Error> {
+    if regressions.is_empty() {
+        writeln!(out, "No regressions")?;
+        return Ok(());
+    }
+
+    writeln!(out, "{} regressions:", regressions.len())?;
+    writeln!(
+        out,
+        "{:>6} {:>12} {:>12}  Template",
+        "Metric", "Baseline", "Current"
+    )?;
+    for regression in regressions {
+        writeln!(
+            out,
+            "{:>6} {:>12} {:>12}  {}",
+            regression.metric,
+            render_seconds(regression.baseline),
+            render_seconds(regression.current),
+            regression.template
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::tests::log_lines;
+    use crate::app::Settings;
+
+    #[test]
+    fn test_check() {
+        let lines = log_lines(&[
+            (0, "start"),
+            (1000, "load 1"),
+            (2000, "load 2"),
+            (2010, "done"),
+        ]);
+        let baseline = Baseline::new(&App::new(&lines, &Settings::default()));
+        assert_eq!(baseline.templates[0].template, "<*> <*> load <*>");
+        assert_eq!(baseline.templates[0].total_ms, 2000);
+
+        let lines = log_lines(&[
+            (0, "start"),
+            (1000, "load 1"),
+            (4000, "load 2"),
+            (4050, "done"),
+            (4060, "cleanup"),
+        ]);
+        let current = Baseline::new(&App::new(&lines, &Settings::default()));
+        let thresholds = Thresholds {
+            percent: 20.0,
+            min_increase: Duration::milliseconds(100),
+        };
+
+        // "done" slowed down by 40ms and "cleanup" is new, but both are under 100ms
+        assert_eq!(
+            check(&baseline, &current, thresholds),
+            vec![
+                Regression {
+                    template: "<*> <*> load <*>".to_string(),
+                    metric: "total",
+                    baseline: Duration::seconds(2),
+                    current: Duration::seconds(4),
+                },
+                Regression {
+                    template: "<*> <*> load <*>".to_string(),
+                    metric: "p99",
+                    baseline: Duration::seconds(1),
+                    current: Duration::seconds(3),
+                },
+            ]
+        );
+        assert!(check(&baseline, &baseline, thresholds).is_empty());
+
+        let mut out = Vec::new();
+        write_regressions(&check(&baseline, &current, thresholds), &mut out).unwrap();
+        let expected = "\
+2 regressions:
+Metric     Baseline      Current  Template
+ total       2.000s       4.000s  <*> <*> load <*>
+   p99       1.000s       3.000s  <*> <*> load <*>
+";
+        assert_eq!(String::from_utf8(out).unwrap(), expected);
+
+        let mut out = Vec::new();
+        write_regressions(&[], &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "No regressions\n");
+    }
+}
@@ -0,0 +1,361 @@
+//! Key bindings, which map keys to commands depending on the active panel
+
+use crate::app::Panel;
+use crossterm::event::KeyCode;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Something the user can do with a key press. Commands are named in snake case
+/// in the config file, e.g. "page_down".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Command {
+    Quit,
+    ToggleHelp,
+    NextPanel,
+    Up,
+    Down,
+    PageUp,
+    PageDown,
+    Left,
+    Right,
+    Home,
+    End,
+    /// Moves to the first line of the log or list
+    Top,
+    /// Moves to the last line of the log or list
+    Bottom,
+    Select,
+    Reset,
+    Back,
+    ToggleAttribution,
+    NextGap,
+    PreviousGap,
+    ShrinkLog,
+    GrowLog,
+    ShrinkLeft,
+    GrowLeft,
+    Maximise,
+    HidePanel,
+    ShowPanels,
+    ToggleTimestamps,
+    ToggleRelativeTimes,
+    SetReference,
+    /// Stretches the log bars, or doubles the length of the largest diffs list
+    Grow,
+    /// Shrinks the log bars, or halves the length of the largest diffs list
+    Shrink,
+    ToggleChartMode,
+    ToggleMarkers,
+    SelectRange,
+    ExportChart,
+    /// Saves the lines selected or visible on the chart, with the diffs panel's key filter
+    ExportLines,
+    CycleKeyFilter,
+    CycleOrder,
+    ToggleSmallest,
+}
+
+impl Command {
+    /// The panels in which the command can be used, or an empty slice if it can be used
+    /// in any panel.
+    pub fn panels(self) -> &'static [Panel] {
+        match self {
+            Command::ToggleTimestamps | Command::ToggleRelativeTimes | Command::SetReference => {
+                &[Panel::Log]
+            }
+            Command::Grow | Command::Shrink => &[Panel::Log, Panel::List],
+            Command::ToggleChartMode
+            | Command::ToggleMarkers
+            | Command::SelectRange
+            | Command::ExportChart => &[Panel::Chart],
+            Command::CycleKeyFilter | Command::CycleOrder | Command::ToggleSmallest => {
+                &[Panel::List]
+            }
+            _ => &[],
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Command> {
+        serde_json::from_value(Value::String(name.to_string())).ok()
+    }
+}
+
+// The default key for each command. Commands not listed here are unbound by default.
+const DEFAULT_KEYS: &[(Command, &str)] = &[
+    (Command::Quit, "q"),
+    (Command::ToggleHelp, "h"),
+    (Command::NextPanel, "Tab"),
+    (Command::Up, "Up"),
+    (Command::Down, "Down"),
+    (Command::PageUp, "PageUp"),
+    (Command::PageDown, "PageDown"),
+    (Command::Left, "Left"),
+    (Command::Right, "Right"),
+    (Command::Home, "Home"),
+    (Command::End, "End"),
+    (Command::Select, "Enter"),
+    (Command::Reset, "Esc"),
+    (Command::Back, "Backspace"),
+    (Command::ToggleAttribution, "a"),
+    (Command::NextGap, "n"),
+    (Command::PreviousGap, "N"),
+    (Command::ShrinkLog, "{"),
+    (Command::GrowLog, "}"),
+    (Command::ShrinkLeft, "<"),
+    (Command::GrowLeft, ">"),
+    (Command::Maximise, "z"),
+    (Command::HidePanel, "x"),
+    (Command::ShowPanels, "X"),
+    (Command::ToggleTimestamps, "d"),
+    (Command::ToggleRelativeTimes, "c"),
+    (Command::SetReference, "r"),
+    (Command::Grow, "+"),
+    (Command::Shrink, "-"),
+    (Command::ToggleChartMode, "t"),
+    (Command::ToggleMarkers, "m"),
+    (Command::SelectRange, "v"),
+    (Command::ExportChart, "e"),
+    (Command::ExportLines, "E"),
+    (Command::CycleKeyFilter, "k"),
+    (Command::CycleOrder, "s"),
+    (Command::ToggleSmallest, "S"),
+];
+
+// Added to the default keys by the vi preset
+const VI_KEYS: &[(Command, &str)] = &[
+    (Command::Down, "j"),
+    (Command::Up, "k"),
+    (Command::Top, "g"),
+    (Command::Bottom, "G"),
+];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Preset {
+    #[default]
+    #[serde(rename = "default")]
+    Default,
+    /// Adds j and k for moving down and up, and g and G for moving to the first and last line
+    #[serde(rename = "vi")]
+    Vi,
+}
+
+/// The "keys" section of the config file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KeyConfig {
+    pub preset: Preset,
+    /// Replaces the preset's keys for each command listed, e.g. `"quit": ["q", "Esc"]`
+    pub bindings: BTreeMap<Command, Vec<String>>,
+    /// If true then the quit key must be pressed twice
+    pub confirm_quit: bool,
+}
+
+impl Default for KeyConfig {
+    fn default() -> Self {
+        KeyConfig {
+            preset: Preset::default(),
+            bindings: BTreeMap::new(),
+            confirm_quit: true,
+        }
+    }
+}
+
+/// Parses a key name: a single character, or one of "Up", "Down", "Left", "Right",
+/// "PageUp", "PageDown", "Home", "End", "Tab", "Enter", "Esc", "Backspace", "Delete",
+/// "Insert", "Space" or "F1" to "F12".
+pub fn parse_key(name: &str) -> Result<KeyCode, failure::Error> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Ok(KeyCode::Char(c));
+    }
+    let key = match name {
+        "Up" => KeyCode::Up,
+        "Down" => KeyCode::Down,
+        "Left" => KeyCode::Left,
+        "Right" => KeyCode::Right,
+        "PageUp" => KeyCode::PageUp,
+        "PageDown" => KeyCode::PageDown,
+        "Home" => KeyCode::Home,
+        "End" => KeyCode::End,
+        "Tab" => KeyCode::Tab,
+        "Enter" => KeyCode::Enter,
+        "Esc" => KeyCode::Esc,
+        "Backspace" => KeyCode::Backspace,
+        "Delete" => KeyCode::Delete,
+        "Insert" => KeyCode::Insert,
+        "Space" => KeyCode::Char(' '),
+        _ if name.starts_with('F') => match name[1..].parse() {
+            Ok(n) if (1..=12).contains(&n) => KeyCode::F(n),
+            _ => return Err(failure::format_err!("Unknown key '{}'", name)),
+        },
+        _ => return Err(failure::format_err!("Unknown key '{}'", name)),
+    };
+    Ok(key)
+}
+
+/// The inverse of `parse_key`.
+pub fn key_name(key: KeyCode) -> String {
+    match key {
+        KeyCode::Char(' ') => "Space".to_string(),
+        KeyCode::Char(c) => c.to_string(),
+        KeyCode::F(n) => format!("F{}", n),
+        other => format!("{:?}", other),
+    }
+}
+
+/// The keys bound to each command.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyBindings {
+    keys: BTreeMap<Command, Vec<KeyCode>>,
+    pub confirm_quit: bool,
+}
+
+impl KeyBindings {
+    pub fn new(config: &KeyConfig) -> Result<KeyBindings, failure::Error> {
+        let preset = match config.preset {
+            Preset::Default => Vec::new(),
+            Preset::Vi => VI_KEYS.to_vec(),
+        };
+        let mut keys: BTreeMap<_, Vec<_>> = BTreeMap::new();
+        for (command, key) in DEFAULT_KEYS.iter().chain(&preset) {
+            keys.entry(*command).or_default().push(parse_key(key)?);
+        }
+        // 'k' is taken by moving up, so filtering by key moves to 'f'
+        if config.preset == Preset::Vi {
+            keys.insert(Command::CycleKeyFilter, vec![KeyCode::Char('f')]);
+        }
+        for (command, names) in &config.bindings {
+            let bound = names
+                .iter()
+                .map(|n| parse_key(n))
+                .collect::<Result<_, _>>()?;
+            keys.insert(*command, bound);
+        }
+
+        Ok(KeyBindings {
+            keys,
+            confirm_quit: config.confirm_quit,
+        })
+    }
+
+    /// The command bound to `key` in `panel`. Commands specific to the panel take
+    /// precedence over commands which can be used in any panel.
+    pub fn command(&self, key: KeyCode, panel: Panel) -> Option<Command> {
+        let bound = self
+            .keys
+            .iter()
+            .filter(|(_, keys)| keys.contains(&key))
+            .map(|(command, _)| *command);
+        let mut global = None;
+        for command in bound {
+            let panels = command.panels();
+            if panels.contains(&panel) {
+                return Some(command);
+            }
+            if panels.is_empty() && global.is_none() {
+                global = Some(command);
+            }
+        }
+        global
+    }
+
+    /// The names of the keys bound to `command`, separated by slashes.
+    pub fn describe(&self, command: Command) -> String {
+        match self.keys.get(&command) {
+            Some(keys) if !keys.is_empty() => keys
+                .iter()
+                .map(|k| key_name(*k))
+                .collect::<Vec<_>>()
+                .join("/"),
+            _ => "(unbound)".to_string(),
+        }
+    }
+
+    /// Replaces command names in braces, e.g. "{page_down}", with the keys bound to them.
+    /// Other text in braces is left alone.
+    pub fn substitute(&self, text: &str) -> String {
+        let mut result = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(start) = rest.find('{') {
+            let name = &rest[start + 1..];
+            let command = name
+                .find('}')
+                .and_then(|end| Command::from_name(&name[..end]).map(|c| (end, c)));
+            match command {
+                Some((end, command)) => {
+                    result.push_str(&rest[..start]);
+                    result.push_str(&self.describe(command));
+                    rest = &rest[start + end + 2..];
+                }
+                None => {
+                    result.push_str(&rest[..=start]);
+                    rest = &rest[start + 1..];
+                }
+            }
+        }
+        result.push_str(rest);
+        result
+    }
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        KeyBindings::new(&KeyConfig::default()).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_bindings() {
+        let bindings = KeyBindings::default();
+        assert_eq!(
+            bindings.command(KeyCode::Char('r'), Panel::Log),
+            Some(Command::SetReference)
+        );
+        assert_eq!(bindings.command(KeyCode::Char('r'), Panel::List), None);
+        assert_eq!(bindings.command(KeyCode::Char('r'), Panel::Chart), None);
+        assert_eq!(
+            bindings.command(KeyCode::Char('S'), Panel::List),
+            Some(Command::ToggleSmallest)
+        );
+        assert_eq!(bindings.command(KeyCode::Char('j'), Panel::Log), None);
+        assert_eq!(bindings.command(KeyCode::Char('g'), Panel::Log), None);
+
+        let config: KeyConfig =
+            serde_json::from_str(r#"{"preset": "vi", "bindings": {"quit": ["Q", "F10"]}}"#)
+                .unwrap();
+        let bindings = KeyBindings::new(&config).unwrap();
+        assert!(bindings.confirm_quit);
+        assert_eq!(
+            bindings.command(KeyCode::Char('k'), Panel::List),
+            Some(Command::Up)
+        );
+        assert_eq!(
+            bindings.command(KeyCode::Char('f'), Panel::List),
+            Some(Command::CycleKeyFilter)
+        );
+        assert_eq!(
+            bindings.command(KeyCode::Char('G'), Panel::Log),
+            Some(Command::Bottom)
+        );
+        assert_eq!(bindings.command(KeyCode::Char('q'), Panel::Log), None);
+        assert_eq!(
+            bindings.substitute("{down} scrolls, {quit} quits, { and {unknown} are kept"),
+            "Down/j scrolls, Q/F10 quits, { and {unknown} are kept"
+        );
+    }
+
+    #[test]
+    fn test_parse_key() {
+        for name in &["q", "{", "PageDown", "Esc", "Space", "F12"] {
+            assert_eq!(key_name(parse_key(name).unwrap()), *name);
+        }
+        assert!(parse_key("F13").is_err());
+        assert!(parse_key("Ctrl").is_err());
+    }
+}
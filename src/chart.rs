@@ -11,8 +11,29 @@ pub struct ChartState {
     pub interval: (usize, usize),
     /// How much to try to multiply interval length by when zooming
     pub zoom_factor: f64,
-    /// The number of horizontal "pixels" available to plot the chart
+    /// The number of horizontal "pixels" available to plot the chart. The interval
+    /// always covers at least this many lines, unless the log is shorter
     pub horizontal_resolution: usize,
+    /// Intervals shown before each change of zoom, most recent last
+    pub zoom_history: Vec<(usize, usize)>,
+}
+
+/// What to plot on the chart's axes.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ChartMode {
+    /// Line number against the cumulative fraction of elapsed time
+    LineNumber,
+    /// Time against line number, i.e. the log's throughput
+    Time,
+}
+
+impl ChartMode {
+    pub fn toggle(self) -> Self {
+        match self {
+            ChartMode::LineNumber => ChartMode::Time,
+            ChartMode::Time => ChartMode::LineNumber,
+        }
+    }
 }
 
 /// The data for a zoomed section of the elapsed time chart.
@@ -26,10 +47,7 @@ pub struct ChartSection {
 impl ChartState {
     pub fn new(deltas: Vec<f64>) -> ChartState {
         assert!(!deltas.is_empty());
-        let mut cumulative_deltas = deltas.clone();
-        for i in 1..cumulative_deltas.len() {
-            cumulative_deltas[i] += cumulative_deltas[i - 1];
-        }
+        let cumulative_deltas = prefix_sum(&deltas);
         let len = deltas.len();
         ChartState {
             deltas,
@@ -37,12 +55,36 @@ impl ChartState {
             interval: (0, len),
             zoom_factor: 3.0,
             horizontal_resolution: 100,
+            zoom_history: Vec::new(),
         }
     }
 
+    /// Replaces the deltas for the same set of lines, preserving the current interval.
+    pub fn set_deltas(&mut self, deltas: Vec<f64>) {
+        assert_eq!(deltas.len(), self.deltas.len());
+        self.cumulative_deltas = prefix_sum(&deltas);
+        self.deltas = deltas;
+    }
+
     pub fn reset_zoom(&mut self) {
         self.zoom_factor = 1.0;
-        self.interval = (0, self.deltas.len());
+        self.set_zoomed_interval((0, self.deltas.len()));
+    }
+
+    /// Returns to the interval shown before the most recent change of zoom, if any,
+    /// widened if the horizontal resolution has grown since it was shown.
+    pub fn zoom_back(&mut self) {
+        if let Some(interval) = self.zoom_history.pop() {
+            self.interval = self.widened(interval.0, interval.1);
+        }
+    }
+
+    // Records the current interval in the zoom history if `interval` differs from it
+    fn set_zoomed_interval(&mut self, interval: (usize, usize)) {
+        if interval != self.interval {
+            self.zoom_history.push(self.interval);
+            self.interval = interval;
+        }
     }
 
     /// If the entire log is visible in the chart then zoom level is 1.0.
@@ -86,36 +128,109 @@ impl ChartState {
         self.interval.1 - self.interval.0
     }
 
+    /// The number of lines between consecutive plotted points.
+    pub fn step_size(&self) -> usize {
+        let resolution = self.horizontal_resolution.max(1);
+        self.interval_length().div_ceil(resolution).max(1)
+    }
+
+    /// Updates the resolution to match the space available to draw the chart,
+    /// widening the current interval if it is now too short.
+    pub fn set_horizontal_resolution(&mut self, horizontal_resolution: usize) {
+        self.horizontal_resolution = horizontal_resolution.max(1);
+        self.interval = self.widened(self.interval.0, self.interval.1);
+    }
+
     pub fn zoom_in(&mut self, current_line: usize) {
-        self.interval = zoom(
+        let interval = zoom(
             current_line,
             self.deltas.len(),
             self.interval,
             self.horizontal_resolution,
             1.0 / self.zoom_factor,
         );
+        self.set_zoomed_interval(interval);
     }
 
     pub fn zoom_out(&mut self, current_line: usize) {
-        self.interval = zoom(
+        let interval = zoom(
             current_line,
             self.deltas.len(),
             self.interval,
             self.horizontal_resolution,
             self.zoom_factor,
         );
+        self.set_zoomed_interval(interval);
+    }
+
+    /// Shows the lines in `[lower, upper)`, widening the interval around its centre if
+    /// necessary so that it covers at least `horizontal_resolution` lines.
+    pub fn zoom_to(&mut self, lower: usize, upper: usize) {
+        let interval = self.widened(lower, upper);
+        self.set_zoomed_interval(interval);
     }
 
+    // Widens `[lower, upper)` around its centre to cover at least `horizontal_resolution`
+    // lines, and clamps it to the extent of the log
+    fn widened(&self, lower: usize, upper: usize) -> (usize, usize) {
+        let num_lines = self.deltas.len();
+        let length = upper
+            .saturating_sub(lower)
+            .max(self.horizontal_resolution)
+            .min(num_lines);
+        let centre = (lower + upper) / 2;
+        let lower = centre.saturating_sub(length / 2).min(num_lines - length);
+        (lower, lower + length)
+    }
+
+    /// Line number against cumulative fraction of elapsed time.
     pub fn section(&self) -> ChartSection {
-        let points: Vec<_> = self
-            .cumulative_deltas
-            .iter()
-            .enumerate()
-            .skip(self.interval.0 as usize)
-            .step_by(self.interval_length() / self.horizontal_resolution)
-            .take(self.horizontal_resolution)
-            .map(|(i, d)| (i as f64, *d))
-            .collect();
+        self.section_of(
+            |i| (i as f64, self.cumulative_deltas[i]),
+            |i| self.deltas[i],
+        )
+    }
+
+    /// Time against line number, where `times` contains the time of each line.
+    pub fn time_section(&self, times: &[f64]) -> ChartSection {
+        assert_eq!(times.len(), self.deltas.len());
+        self.section_of(
+            |i| (times[i], i as f64),
+            |i| if i == 0 { 0.0 } else { times[i] - times[i - 1] },
+        )
+    }
+
+    // Splits the interval into buckets of `step_size` lines and plots the first line of each
+    // bucket, along with the lines either side of the largest step within it, as measured by
+    // `step`. This keeps large gaps visible however far the chart is zoomed out. Plots every
+    // line if the interval is no longer than the horizontal resolution.
+    fn section_of<P, S>(&self, point: P, step: S) -> ChartSection
+    where
+        P: Fn(usize) -> (f64, f64),
+        S: Fn(usize) -> f64,
+    {
+        let (lower, upper) = self.interval;
+        let step_size = self.step_size();
+
+        let mut lines = Vec::new();
+        for start in (lower..upper).step_by(step_size) {
+            lines.push(start);
+            if step_size > 1 {
+                let end = (start + step_size).min(upper);
+                let largest =
+                    (start..end).fold(start, |l, i| if step(i) > step(l) { i } else { l });
+                if largest > lower {
+                    lines.push(largest - 1);
+                }
+                lines.push(largest);
+            }
+        }
+        // Always plot the end of the interval
+        lines.push(upper - 1);
+        lines.sort();
+        lines.dedup();
+
+        let points: Vec<_> = lines.into_iter().map(point).collect();
 
         let first = points[0];
         let last = points[points.len() - 1];
@@ -131,6 +246,14 @@ impl ChartState {
     }
 }
 
+fn prefix_sum(values: &[f64]) -> Vec<f64> {
+    let mut sums = values.to_vec();
+    for i in 1..sums.len() {
+        sums[i] += sums[i - 1];
+    }
+    sums
+}
+
 // A `scale_factor` < 1.0 decreases the interval length, i.e. zooms in.
 fn zoom(
     current_line: usize,
@@ -150,7 +273,13 @@ fn zoom(
         return (0, num_lines);
     }
 
+    let horizontal_resolution = horizontal_resolution.max(1);
     let current_step_size = current_interval_length / horizontal_resolution;
+    // The interval can be shorter than the resolution, e.g. when the whole log is, and then
+    // there are no steps to scale
+    if current_step_size == 0 {
+        return interval;
+    }
     let current_lower_offset = (current_line - interval.0) / current_step_size;
     let current_upper_offset = (interval.1 - current_line) / current_step_size;
     let target_step_size = target_interval_length / horizontal_resolution;
@@ -186,10 +315,98 @@ mod tests {
                 interval: (0, 4),
                 zoom_factor: 3.0,
                 horizontal_resolution: 100,
+                zoom_history: Vec::new(),
+            }
+        );
+    }
+
+    #[test]
+    fn section_of_short_log() {
+        let state = ChartState::new(vec![0.25; 4]);
+        assert_eq!(
+            state.section(),
+            ChartSection {
+                points: vec![(0.0, 0.25), (1.0, 0.5), (2.0, 0.75), (3.0, 1.0)],
+                x_bounds: (0.0, 3.0),
+                y_bounds: (0.25, 1.0),
             }
         );
     }
 
+    #[test]
+    fn section_of_includes_interval_end() {
+        let mut state = ChartState::new(vec![0.001; 1000]);
+        state.set_horizontal_resolution(300);
+        let section = state.section();
+        assert_eq!(state.step_size(), 4);
+        assert_eq!(section.x_bounds, (0.0, 999.0));
+        assert!(section.points.windows(2).all(|w| w[0].0 < w[1].0));
+    }
+
+    #[test]
+    fn section_of_keeps_largest_step() {
+        let mut deltas = vec![0.0; 1000];
+        deltas[503] = 1.0;
+        let state = ChartState::new(deltas);
+        assert_eq!(state.step_size(), 10);
+
+        // The step lies between the first lines of two buckets, so would be
+        // drawn as a shallow slope if only those lines were plotted
+        let points = state.section().points;
+        assert!(points.contains(&(502.0, 0.0)));
+        assert!(points.contains(&(503.0, 1.0)));
+    }
+
+    #[test]
+    fn set_horizontal_resolution() {
+        let mut state = ChartState::new(vec![0.001; 1000]);
+        state.zoom_to(400, 500);
+        state.set_horizontal_resolution(200);
+        assert_eq!(state.interval, (350, 550));
+        state.set_horizontal_resolution(2000);
+        assert_eq!(state.interval, (0, 1000));
+        state.set_horizontal_resolution(0);
+        assert_eq!(state.horizontal_resolution, 1);
+    }
+
+    #[test]
+    fn chart_state_zoom_to() {
+        let mut state = ChartState::new(vec![0.001; 1000]);
+        state.zoom_to(200, 500);
+        assert_eq!(state.interval, (200, 500));
+        // Widened to the horizontal resolution
+        state.zoom_to(400, 410);
+        assert_eq!(state.interval, (355, 455));
+        // Clamped to the extent of the log
+        state.zoom_to(980, 1000);
+        assert_eq!(state.interval, (900, 1000));
+        state.zoom_to(0, 5000);
+        assert_eq!(state.interval, (0, 1000));
+
+        state.zoom_back();
+        assert_eq!(state.interval, (900, 1000));
+        state.zoom_back();
+        assert_eq!(state.interval, (355, 455));
+        state.zoom_back();
+        state.zoom_back();
+        assert_eq!(state.interval, (0, 1000));
+        // No history left
+        state.zoom_back();
+        assert_eq!(state.interval, (0, 1000));
+    }
+
+    #[test]
+    fn zoom_back_after_resize() {
+        let mut state = ChartState::new(vec![0.001; 1000]);
+        state.zoom_to(400, 500);
+        state.zoom_to(200, 800);
+        state.set_horizontal_resolution(200);
+        assert_eq!(state.interval, (200, 800));
+        // The previous interval is narrower than the new resolution
+        state.zoom_back();
+        assert_eq!(state.interval, (350, 550));
+    }
+
     #[derive(Debug)]
     struct ZoomTestCase {
         description: Option<String>,
@@ -248,6 +465,15 @@ mod tests {
                 zoom_factor: 0.5,
                 expected_interval: (150, 1140),
             },
+            ZoomTestCase {
+                description: Some("Interval shorter than the resolution".into()),
+                current_line: 2,
+                num_lines: 10,
+                interval: (0, 4),
+                horizontal_resolution: 5,
+                zoom_factor: 2.0,
+                expected_interval: (0, 4),
+            },
         ];
         for test_case in test_cases {
             test_case.run();
@@ -0,0 +1,265 @@
+//! Aligns two runs of the same job and compares the time taken by each step
+
+use crate::app::{render_seconds, render_seconds_delta, App};
+use crate::svg;
+use chrono::Duration;
+use regex::Regex;
+use std::collections::HashMap;
+use std::io::Write;
+
+/// How lines in one log are matched with lines in the other.
+#[derive(Debug, Clone)]
+pub enum Alignment {
+    /// The nth line with a given template in one log matches the nth line with
+    /// the same template in the other
+    Templates,
+    /// Only lines matching one of these patterns are aligned, and the nth line
+    /// matching a pattern in one log matches the nth line matching it in the other
+    Anchors(Vec<Regex>),
+}
+
+/// A pair of aligned lines, and the time taken to reach them from the previous
+/// pair of aligned lines in each log.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step {
+    /// The template of the line in the first log
+    pub name: String,
+    pub lines: (usize, usize),
+    pub elapsed: (Duration, Duration),
+}
+
+impl Step {
+    /// Positive if the step took longer in the second log.
+    pub fn delta(&self) -> Duration {
+        self.elapsed.1 - self.elapsed.0
+    }
+}
+
+// Identifies the lines to align, in order, by the pattern they match and how many earlier
+// lines matched the same pattern
+fn step_keys(app: &App, alignment: &Alignment) -> Vec<((String, usize), usize)> {
+    let mut occurrences = HashMap::new();
+    let mut keys = Vec::new();
+
+    for line in &app.lines {
+        let pattern = match alignment {
+            Alignment::Templates => {
+                Some(app.templates.names[app.templates.ids[line.line_number]].clone())
+            }
+            Alignment::Anchors(anchors) => anchors
+                .iter()
+                .position(|a| a.is_match(line.line))
+                .map(|i| i.to_string()),
+        };
+        if let Some(pattern) = pattern {
+            let occurrence = occurrences.entry(pattern.clone()).or_insert(0);
+            keys.push(((pattern, *occurrence), line.line_number));
+            *occurrence += 1;
+        }
+    }
+
+    keys
+}
+
+/// Aligns the lines of `first` and `second`. Aligned lines are kept in the order they
+/// occur in both logs, so lines which would need to be reordered are skipped.
+pub fn align(first: &App, second: &App, alignment: &Alignment) -> Vec<Step> {
+    let second_lines: HashMap<_, _> = step_keys(second, alignment).into_iter().collect();
+
+    let mut steps = Vec::new();
+    let mut previous = (0, 0);
+    for (key, line) in step_keys(first, alignment) {
+        let other_line = match second_lines.get(&key) {
+            Some(l) if steps.is_empty() || *l > previous.1 => *l,
+            _ => continue,
+        };
+        steps.push(Step {
+            name: first.templates.names[first.templates.ids[line]].clone(),
+            lines: (line, other_line),
+            elapsed: (
+                first.lines[line].timestamp - first.lines[previous.0].timestamp,
+                second.lines[other_line].timestamp - second.lines[previous.1].timestamp,
+            ),
+        });
+        previous = (line, other_line);
+    }
+
+    steps
+}
+
+/// Cumulative elapsed time in seconds in each log after each step.
+pub fn cumulative_seconds(steps: &[Step]) -> (Vec<f64>, Vec<f64>) {
+    let mut totals = (0.0, 0.0);
+    steps
+        .iter()
+        .map(|s| {
+            totals.0 += s.elapsed.0.num_milliseconds() as f64 / 1000.0;
+            totals.1 += s.elapsed.1.num_milliseconds() as f64 / 1000.0;
+            totals
+        })
+        .unzip()
+}
+
+// The maximum number of points plotted for each log
+const MAX_CHART_POINTS: usize = 2000;
+
+/// Renders the cumulative elapsed time in each log against step number as an SVG image.
+pub fn comparison_svg(steps: &[Step], width: usize, height: usize) -> String {
+    let (first, second) = cumulative_seconds(steps);
+    let stride = (steps.len() / MAX_CHART_POINTS).max(1);
+    let points = |totals: &[f64]| -> Vec<(f64, f64)> {
+        let mut indices: Vec<_> = (0..totals.len()).step_by(stride).collect();
+        // Always plot the final totals
+        match indices.last() {
+            Some(i) if *i + 1 < totals.len() => indices.push(totals.len() - 1),
+            _ => {}
+        }
+        indices.into_iter().map(|i| (i as f64, totals[i])).collect()
+    };
+    let (first_points, second_points) = (points(&first), points(&second));
+
+    let last_step = steps.len().saturating_sub(1) as f64;
+    let max_seconds = first.iter().chain(&second).cloned().fold(0.0, f64::max);
+    let labels = |max: f64, precision: usize| -> Vec<String> {
+        (0..5)
+            .map(|i| format!("{:.*}", precision, max * i as f64 / 4.0))
+            .collect()
+    };
+
+    svg::chart(
+        &svg::Chart {
+            series: &[
+                svg::Series {
+                    name: "First log",
+                    points: &first_points,
+                    colour: svg::CYAN,
+                },
+                svg::Series {
+                    name: "Second log",
+                    points: &second_points,
+                    colour: svg::GREEN,
+                },
+            ],
+            x_bounds: (0.0, last_step),
+            y_bounds: (0.0, max_seconds),
+            x_labels: &labels(last_step, 0),
+            y_labels: &labels(max_seconds, 1),
+            x_title: "Step",
+            y_title: "Cumulative elapsed time (seconds)",
+            current: None,
+            markers: &[],
+        },
+        width,
+        height,
+    )
+}
+
+/// Writes the total time taken in each log and the `top` steps which slowed down the most.
+pub fn write_comparison<W: Write + ?Sized>(
+    first: &App,
+    second: &App,
+    steps: &[Step],
+    top: usize,
+    out: &mut W,
+) -> Result<(), failure::Error> {
+    let total = |app: &App| app.lines[app.lines.len() - 1].timestamp - app.lines[0].timestamp;
+    let (first_total, second_total) = (total(first), total(second));
+
+    writeln!(
+        out,
+        "Aligned {} steps, from {} lines in the first log and {} lines in the second",
+        steps.len(),
+        first.lines.len(),
+        second.lines.len()
+    )?;
+    writeln!(
+        out,
+        "Duration: {} vs {} ({})",
+        render_seconds(first_total),
+        render_seconds(second_total),
+        render_seconds_delta(second_total - first_total)
+    )?;
+
+    let mut slowest: Vec<_> = steps.iter().collect();
+    slowest.sort_by(|x, y| y.delta().cmp(&x.delta()).then(x.lines.cmp(&y.lines)));
+
+    writeln!(out)?;
+    writeln!(out, "Largest increases in elapsed time:")?;
+    writeln!(
+        out,
+        "{:>10} {:>10} {:>12} {:>12} {:>12}  Step",
+        "Line (1)", "Line (2)", "Elapsed (1)", "Elapsed (2)", "Delta"
+    )?;
+    for step in slowest.iter().take(top) {
+        writeln!(
+            out,
+            "{:>10} {:>10} {:>12} {:>12} {:>12}  {}",
+            step.lines.0,
+            step.lines.1,
+            render_seconds(step.elapsed.0),
+            render_seconds(step.elapsed.1),
+            render_seconds_delta(step.delta()),
+            step.name
+        )?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::tests::log_lines;
+    use crate::app::Settings;
+
+    #[test]
+    fn test_align() {
+        let first = log_lines(&[
+            (0, "start"),
+            (1000, "load 1"),
+            (2000, "load 2"),
+            (3000, "done"),
+        ]);
+        let second = log_lines(&[
+            (0, "start"),
+            (1000, "load 1"),
+            (1500, "unexpected"),
+            (5000, "load 2"),
+            (6000, "done"),
+        ]);
+        let settings = Settings::default();
+        let (first, second) = (App::new(&first, &settings), App::new(&second, &settings));
+
+        let steps = align(&first, &second, &Alignment::Templates);
+        let lines: Vec<_> = steps.iter().map(|s| s.lines).collect();
+        assert_eq!(lines, vec![(0, 0), (1, 1), (2, 3), (3, 4)]);
+        assert_eq!(steps[2].name, "<*> <*> load <*>");
+        assert_eq!(steps[2].delta(), Duration::seconds(3));
+        assert_eq!(
+            cumulative_seconds(&steps),
+            (vec![0.0, 1.0, 2.0, 3.0], vec![0.0, 1.0, 5.0, 6.0])
+        );
+
+        let mut out = Vec::new();
+        write_comparison(&first, &second, &steps, 2, &mut out).unwrap();
+        let expected = "\
+Aligned 4 steps, from 4 lines in the first log and 5 lines in the second
+Duration: 3.000s vs 6.000s (+3.000s)
+
+Largest increases in elapsed time:
+  Line (1)   Line (2)  Elapsed (1)  Elapsed (2)        Delta  Step
+         2          3       1.000s       4.000s      +3.000s  <*> <*> load <*>
+         0          0       0.000s       0.000s      +0.000s  <*> <*> start
+";
+        assert_eq!(String::from_utf8(out).unwrap(), expected);
+
+        let anchors = Alignment::Anchors(vec![Regex::new("start|done").unwrap()]);
+        let steps = align(&first, &second, &anchors);
+        let lines: Vec<_> = steps.iter().map(|s| s.lines).collect();
+        assert_eq!(lines, vec![(0, 0), (3, 4)]);
+        assert_eq!(
+            steps[1].elapsed,
+            (Duration::seconds(3), Duration::seconds(6))
+        );
+    }
+}
@@ -0,0 +1,147 @@
+//! User preferences which persist between runs, stored as JSON in
+//! `$XDG_CONFIG_HOME/lag/config.json`
+
+use crate::bindings::KeyConfig;
+use crate::theme::ThemeName;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+// Limits on panel sizes, as percentages, so that no panel can be resized out of view
+const MIN_SIZE: u16 = 10;
+const MAX_SIZE: u16 = 90;
+
+/// The sizes and visibility of the panels. The log panel is shown above a row containing
+/// a panel on the left (the chart, log rate or distribution panel) and one on the right
+/// (the largest diffs, spans or templates panel).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PanelLayout {
+    /// Height of the log panel as a percentage of the space for panels
+    pub log_height: u16,
+    /// Width of the left panel as a percentage of the bottom row
+    pub left_width: u16,
+    pub hide_log: bool,
+    pub hide_left: bool,
+    pub hide_right: bool,
+}
+
+impl Default for PanelLayout {
+    fn default() -> Self {
+        PanelLayout {
+            log_height: 70,
+            left_width: 37,
+            hide_log: false,
+            hide_left: false,
+            hide_right: false,
+        }
+    }
+}
+
+impl PanelLayout {
+    pub fn resize_log(&mut self, delta: i16) {
+        self.log_height = resized(self.log_height, delta);
+    }
+
+    pub fn resize_left(&mut self, delta: i16) {
+        self.left_width = resized(self.left_width, delta);
+    }
+
+    /// Clamps the panel sizes to the limits used when resizing, e.g. after reading the
+    /// layout from the config file.
+    pub fn validated(self) -> PanelLayout {
+        PanelLayout {
+            log_height: resized(self.log_height, 0),
+            left_width: resized(self.left_width, 0),
+            ..self
+        }
+    }
+
+    pub fn show_bottom_row(&self) -> bool {
+        !(self.hide_left && self.hide_right)
+    }
+}
+
+fn resized(size: u16, delta: i16) -> u16 {
+    (size.min(MAX_SIZE) as i16 + delta).clamp(MIN_SIZE as i16, MAX_SIZE as i16) as u16
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub layout: PanelLayout,
+    /// "dark", "light", "256" or "monochrome". Setting the NO_COLOR environment variable
+    /// selects the monochrome theme regardless.
+    pub theme: ThemeName,
+    pub keys: KeyConfig,
+}
+
+impl Config {
+    /// `$XDG_CONFIG_HOME/lag/config.json`, falling back to `$HOME/.config/lag/config.json`.
+    pub fn path() -> Option<PathBuf> {
+        let config_home = match std::env::var_os("XDG_CONFIG_HOME") {
+            Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+        };
+        Some(config_home.join("lag").join("config.json"))
+    }
+
+    /// Reads the config file, or returns the default config if it doesn't exist.
+    pub fn load() -> Result<Config, failure::Error> {
+        let path = match Config::path() {
+            Some(path) if path.exists() => path,
+            _ => return Ok(Config::default()),
+        };
+        let contents = std::fs::read_to_string(&path)?;
+        Config::parse(&contents)
+            .map_err(|e| failure::format_err!("Invalid config file {}: {}", path.display(), e))
+    }
+
+    fn parse(contents: &str) -> Result<Config, serde_json::Error> {
+        let config: Config = serde_json::from_str(contents)?;
+        Ok(Config {
+            layout: config.layout.validated(),
+            ..config
+        })
+    }
+
+    pub fn save(&self) -> Result<(), failure::Error> {
+        let path = Config::path()
+            .ok_or_else(|| failure::err_msg("Neither XDG_CONFIG_HOME nor HOME is set"))?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(&path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config() {
+        // Missing fields take their default values
+        let config = Config::parse(r#"{"layout": {"hide_log": true}}"#).unwrap();
+        assert_eq!(
+            config.layout,
+            PanelLayout {
+                hide_log: true,
+                ..PanelLayout::default()
+            }
+        );
+
+        let mut layout = PanelLayout::default();
+        layout.resize_log(-100);
+        assert_eq!(layout.log_height, MIN_SIZE);
+        layout.resize_left(5);
+        assert_eq!(layout.left_width, 42);
+        layout.resize_left(100);
+        assert_eq!(layout.left_width, MAX_SIZE);
+
+        // Sizes in the config file are clamped, so that they can't overflow when rendering
+        let config = Config::parse(r#"{"layout": {"log_height": 150, "left_width": 0}}"#).unwrap();
+        assert_eq!(config.layout.log_height, MAX_SIZE);
+        assert_eq!(config.layout.left_width, MIN_SIZE);
+    }
+}
@@ -0,0 +1,220 @@
+//! Exports annotated log lines as CSV or JSON lines, for analysis in other tools
+
+use crate::app::{AnnotatedLine, App};
+use chrono::SecondsFormat;
+use serde::Serialize;
+use std::io::Write;
+use std::path::Path;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    JsonLines,
+}
+
+impl std::str::FromStr for ExportFormat {
+    type Err = failure::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "csv" => Ok(ExportFormat::Csv),
+            "jsonl" => Ok(ExportFormat::JsonLines),
+            _ => Err(failure::format_err!(
+                "Invalid export format '{}', expected 'csv' or 'jsonl'",
+                s
+            )),
+        }
+    }
+}
+
+impl ExportFormat {
+    /// JSON lines if `path` has the extension "jsonl", and CSV otherwise.
+    pub fn from_path(path: &Path) -> ExportFormat {
+        match path.extension() {
+            Some(ext) if ext == "jsonl" => ExportFormat::JsonLines,
+            _ => ExportFormat::Csv,
+        }
+    }
+}
+
+/// Restricts the exported lines.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ExportFilter {
+    /// Inclusive range of line numbers
+    pub range: Option<(usize, usize)>,
+    /// Only export lines with this key
+    pub key: Option<String>,
+}
+
+impl ExportFilter {
+    /// The lines currently selected on the chart, or the lines visible on the chart if
+    /// there is no selection, restricted to the key filter of the diffs panel.
+    pub fn current(app: &App) -> ExportFilter {
+        let (lower, upper) = app.chart_state.interval;
+        ExportFilter {
+            range: Some(app.chart_selection().unwrap_or((lower, upper - 1))),
+            key: app.key_name(app.key_filter).map(|k| k.to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Record<'a> {
+    line_number: usize,
+    timestamp: String,
+    elapsed_ms: i64,
+    key: Option<&'a str>,
+    text: &'a str,
+}
+
+impl<'a> Record<'a> {
+    fn new(app: &'a App, line: &'a AnnotatedLine) -> Record<'a> {
+        Record {
+            line_number: line.line_number,
+            timestamp: line.timestamp.to_rfc3339_opts(SecondsFormat::Millis, true),
+            elapsed_ms: line.elapsed.num_milliseconds(),
+            key: app.key_name(line.key),
+            text: line.line,
+        }
+    }
+}
+
+// Quotes a CSV field if it contains a separator, quote or line break
+fn csv_field(field: &str) -> String {
+    if field.contains(&[',', '"', '\n', '\r'][..]) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Writes the lines matching `filter`, with their timestamps and elapsed times, to `out`.
+pub fn export<W: Write + ?Sized>(
+    app: &App,
+    filter: &ExportFilter,
+    format: ExportFormat,
+    out: &mut W,
+) -> Result<(), failure::Error> {
+    let key = match &filter.key {
+        Some(name) => match app.keys.names.iter().position(|n| n == name) {
+            Some(id) => Some(id),
+            None => return Err(failure::format_err!("Unknown key '{}'", name)),
+        },
+        None => None,
+    };
+    let (first, last) = filter.range.unwrap_or((0, app.lines.len() - 1));
+
+    let lines = app
+        .lines
+        .iter()
+        .skip(first)
+        .take((last + 1).saturating_sub(first))
+        .filter(|l| key.is_none() || l.key == key);
+
+    if format == ExportFormat::Csv {
+        writeln!(out, "line_number,timestamp,elapsed_ms,key,text")?;
+    }
+    for line in lines {
+        let record = Record::new(app, line);
+        match format {
+            ExportFormat::Csv => writeln!(
+                out,
+                "{},{},{},{},{}",
+                record.line_number,
+                record.timestamp,
+                record.elapsed_ms,
+                csv_field(record.key.unwrap_or("")),
+                csv_field(record.text)
+            )?,
+            ExportFormat::JsonLines => writeln!(out, "{}", serde_json::to_string(&record)?)?,
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::tests::log_lines;
+    use crate::app::Settings;
+    use crate::keys::KeyExtractor;
+    use regex::Regex;
+
+    fn export_to_string(app: &App, filter: &ExportFilter, format: ExportFormat) -> String {
+        let mut out = Vec::new();
+        export(app, filter, format, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_export() {
+        let lines = log_lines(&[
+            (0, "[a] start"),
+            (1000, "[b] say \"hi\", then"),
+            (1500, "[a] done"),
+        ]);
+        let settings = Settings {
+            key_extractor: Some(KeyExtractor::Regex(Regex::new(r"\[(\w+)\]").unwrap())),
+            ..Settings::default()
+        };
+        let app = App::new(&lines, &settings);
+
+        assert_eq!(
+            export_to_string(&app, &ExportFilter::default(), ExportFormat::Csv),
+            "\
+line_number,timestamp,elapsed_ms,key,text
+0,2020-02-29T10:00:00.000Z,0,a,2020-02-29 10:00:00.000Z [a] start
+1,2020-02-29T10:00:01.000Z,0,b,\"2020-02-29 10:00:01.000Z [b] say \"\"hi\"\", then\"
+2,2020-02-29T10:00:01.500Z,1500,a,2020-02-29 10:00:01.500Z [a] done
+"
+        );
+
+        let filter = ExportFilter {
+            range: Some((1, 2)),
+            key: Some("a".into()),
+        };
+        assert_eq!(
+            export_to_string(&app, &filter, ExportFormat::JsonLines),
+            r#"{"line_number":2,"timestamp":"2020-02-29T10:00:01.500Z","elapsed_ms":1500,"key":"a","text":"2020-02-29 10:00:01.500Z [a] done"}
+"#
+        );
+    }
+
+    #[test]
+    fn test_current_filter() {
+        let lines = log_lines(&[(0, "[a] start"), (1000, "[b] middle"), (2000, "[a] end")]);
+        let settings = Settings {
+            key_extractor: Some(KeyExtractor::Regex(Regex::new(r"\[(\w+)\]").unwrap())),
+            ..Settings::default()
+        };
+        let mut app = App::new(&lines, &settings);
+        assert_eq!(
+            ExportFilter::current(&app),
+            ExportFilter {
+                range: Some((0, 2)),
+                key: None,
+            }
+        );
+
+        app.key_filter = Some(1);
+        app.chart_selection_start = Some(2);
+        app.log_cursor.y = 1;
+        assert_eq!(
+            ExportFilter::current(&app),
+            ExportFilter {
+                range: Some((1, 2)),
+                key: Some("b".to_string()),
+            }
+        );
+
+        assert_eq!(
+            ExportFormat::from_path(Path::new("lines.jsonl")),
+            ExportFormat::JsonLines
+        );
+        assert_eq!(
+            ExportFormat::from_path(Path::new("lines")),
+            ExportFormat::Csv
+        );
+    }
+}
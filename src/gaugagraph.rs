@@ -20,6 +20,10 @@ pub struct Gaugagraph<'a, 't> {
     ratios: Vec<f64>,
     /// Bar lengths are increased by this factor
     zoom: f64,
+    /// Style for text which does not have a bar behind it, on highlighted lines
+    highlight_style: Style,
+    /// Whether each line is highlighted
+    highlighted: Vec<bool>,
 }
 
 impl<'a, 't> Gaugagraph<'a, 't> {
@@ -37,9 +41,17 @@ impl<'a, 't> Gaugagraph<'a, 't> {
             lines,
             ratios,
             zoom,
+            highlight_style: base_style,
+            highlighted: Vec::new(),
         }
     }
 
+    pub fn highlight(mut self, style: Style, highlighted: Vec<bool>) -> Gaugagraph<'a, 't> {
+        self.highlight_style = style;
+        self.highlighted = highlighted;
+        self
+    }
+
     pub fn block(mut self, block: Block<'a>) -> Gaugagraph<'a, 't> {
         self.block = Some(block);
         self
@@ -66,6 +78,11 @@ impl<'a, 't, 'b> Widget for Gaugagraph<'a, 't> {
         let mut y = 0;
         for current_line in self.lines.iter() {
             let bar_end = (self.ratios[y as usize] * text_area.width as f64 * self.zoom) as u16;
+            let base_style = if self.highlighted.get(y as usize) == Some(&true) {
+                self.highlight_style
+            } else {
+                self.base_style
+            };
             let mut x = 0;
 
             for symbol in UnicodeSegmentation::graphemes(*current_line, true) {
@@ -76,7 +93,7 @@ impl<'a, 't, 'b> Widget for Gaugagraph<'a, 't> {
                 let style = if x < bar_end {
                     self.bar_style
                 } else {
-                    self.base_style
+                    base_style
                 };
 
                 buf.get_mut(text_area.left() + x, text_area.top() + y)
@@ -0,0 +1,264 @@
+//! Handles logic for bucketing and zooming the histogram of log lines over time,
+//! and for bucketing elapsed times on a logarithmic scale
+
+// Bucket widths in seconds which make for readable axis labels
+const NICE_WIDTHS: [f64; 27] = [
+    0.001, 0.002, 0.005, 0.01, 0.02, 0.05, 0.1, 0.2, 0.5, 1.0, 2.0, 5.0, 10.0, 15.0, 30.0, 60.0,
+    120.0, 300.0, 600.0, 900.0, 1800.0, 3600.0, 7200.0, 10800.0, 21600.0, 43200.0, 86400.0,
+];
+
+/// The smallest "nice" bucket width that covers `length` seconds in at most `max_buckets` buckets.
+pub fn bucket_width(length: f64, max_buckets: usize) -> f64 {
+    let min_width = length / max_buckets.max(1) as f64;
+    match NICE_WIDTHS.iter().find(|w| **w >= min_width) {
+        Some(w) => *w,
+        None => (min_width / 86400.0).ceil() * 86400.0,
+    }
+}
+
+/// Counts of log lines in consecutive time buckets of equal width.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RateHistogram {
+    /// Start of the first bucket, in seconds since the first line
+    pub start: f64,
+    /// Width of each bucket in seconds
+    pub width: f64,
+    pub counts: Vec<u64>,
+}
+
+impl RateHistogram {
+    /// Counts the `times` that fall within `interval`. Buckets are aligned to
+    /// multiples of `width`, so the first bucket may start before the interval.
+    pub fn new(times: &[f64], interval: (f64, f64), width: f64) -> RateHistogram {
+        let start = (interval.0 / width).floor() * width;
+        let num_buckets = ((interval.1 - start) / width).floor() as usize + 1;
+        let mut counts = vec![0; num_buckets];
+
+        for t in times {
+            if *t >= start && *t <= interval.1 {
+                let bucket = ((t - start) / width) as usize;
+                counts[bucket.min(num_buckets - 1)] += 1;
+            }
+        }
+
+        RateHistogram {
+            start,
+            width,
+            counts,
+        }
+    }
+
+    /// The bucket containing `time`, if any.
+    pub fn bucket(&self, time: f64) -> Option<usize> {
+        if time < self.start {
+            return None;
+        }
+        let bucket = ((time - self.start) / self.width) as usize;
+        if bucket < self.counts.len() {
+            Some(bucket)
+        } else {
+            None
+        }
+    }
+
+    pub fn end(&self) -> f64 {
+        self.start + self.width * self.counts.len() as f64
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RateState {
+    /// Seconds since the first line of the first and last lines
+    pub extent: (f64, f64),
+    /// The currently visible time range, in seconds since the first line
+    pub interval: (f64, f64),
+    /// How much to multiply interval length by when zooming
+    pub zoom_factor: f64,
+    /// The number of buckets available to plot the histogram
+    pub horizontal_resolution: usize,
+}
+
+impl RateState {
+    pub fn new(extent: (f64, f64)) -> RateState {
+        RateState {
+            extent,
+            interval: extent,
+            zoom_factor: 3.0,
+            horizontal_resolution: 100,
+        }
+    }
+
+    pub fn reset_zoom(&mut self) {
+        self.interval = self.extent;
+    }
+
+    pub fn interval_length(&self) -> f64 {
+        self.interval.1 - self.interval.0
+    }
+
+    /// If the entire log is visible then zoom level is 1.0.
+    pub fn current_zoom_level(&self) -> f64 {
+        (self.extent.1 - self.extent.0) / self.interval_length()
+    }
+
+    pub fn bucket_width(&self) -> f64 {
+        bucket_width(self.interval_length(), self.horizontal_resolution)
+    }
+
+    pub fn histogram(&self, times: &[f64]) -> RateHistogram {
+        RateHistogram::new(times, self.interval, self.bucket_width())
+    }
+
+    /// Slide the current interval if `current_time` is outside it.
+    pub fn update(&mut self, current_time: f64) {
+        let length = self.interval_length();
+        if current_time < self.interval.0 {
+            self.interval = (current_time, current_time + length);
+        } else if current_time > self.interval.1 {
+            self.interval = (current_time - length, current_time);
+        }
+    }
+
+    pub fn zoom_in(&mut self, current_time: f64) {
+        self.zoom(current_time, 1.0 / self.zoom_factor);
+    }
+
+    pub fn zoom_out(&mut self, current_time: f64) {
+        self.zoom(current_time, self.zoom_factor);
+    }
+
+    // Scales the interval around `current_time`, keeping its relative position
+    // within the interval unchanged where possible.
+    fn zoom(&mut self, current_time: f64, scale_factor: f64) {
+        let (lower, upper) = self.interval;
+        let extent_length = self.extent.1 - self.extent.0;
+        // Don't zoom in beyond one bucket per millisecond
+        let min_length = 0.001 * self.horizontal_resolution as f64;
+        let target_length = (self.interval_length() * scale_factor)
+            .max(min_length)
+            .min(extent_length);
+
+        let current_time = current_time.max(lower).min(upper);
+        let position = (current_time - lower) / self.interval_length();
+        let target_lower = (current_time - position * target_length)
+            .max(self.extent.0)
+            .min(self.extent.1 - target_length);
+
+        self.interval = (target_lower, target_lower + target_length);
+    }
+}
+
+/// Counts of non-zero elapsed times in buckets of equal width on a logarithmic scale.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ElapsedHistogram {
+    /// Base 10 logarithms of the lower bound of the first bucket and upper bound
+    /// of the last bucket, in milliseconds
+    pub log_bounds: (f64, f64),
+    pub counts: Vec<u64>,
+    /// The number of zero elapsed times, which cannot be shown on a log scale
+    pub zeros: u64,
+}
+
+impl ElapsedHistogram {
+    pub fn new(elapsed_millis: &[f64], num_buckets: usize) -> ElapsedHistogram {
+        let num_buckets = num_buckets.max(1);
+        let (min, max) = elapsed_millis
+            .iter()
+            .filter(|e| **e > 0.0)
+            .fold((f64::INFINITY, 0.0f64), |(min, max), e| {
+                (min.min(*e), max.max(*e))
+            });
+
+        let log_bounds = if max == 0.0 {
+            (0.0, 1.0)
+        } else if min == max {
+            (min.log10() - 0.5, max.log10() + 0.5)
+        } else {
+            (min.log10(), max.log10())
+        };
+
+        let mut histogram = ElapsedHistogram {
+            log_bounds,
+            counts: vec![0; num_buckets],
+            zeros: 0,
+        };
+
+        for e in elapsed_millis {
+            match histogram.bucket(*e) {
+                Some(b) => histogram.counts[b] += 1,
+                None => histogram.zeros += 1,
+            }
+        }
+
+        histogram
+    }
+
+    /// The bucket containing `millis`, or `None` if it is zero.
+    pub fn bucket(&self, millis: f64) -> Option<usize> {
+        if millis <= 0.0 {
+            return None;
+        }
+        let (lower, upper) = self.log_bounds;
+        let position = (millis.log10() - lower) / (upper - lower);
+        let bucket = (position * self.counts.len() as f64).floor().max(0.0) as usize;
+        Some(bucket.min(self.counts.len() - 1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bucket_width() {
+        assert_eq!(bucket_width(10.0, 100), 0.1);
+        assert_eq!(bucket_width(11.0, 100), 0.2);
+        assert_eq!(bucket_width(3600.0, 100), 60.0);
+        assert_eq!(bucket_width(30.0 * 86400.0, 10), 3.0 * 86400.0);
+    }
+
+    #[test]
+    fn test_rate_histogram() {
+        let times = vec![0.0, 0.5, 1.2, 1.3, 1.9, 4.0, 5.5];
+        let histogram = RateHistogram::new(&times, (0.5, 5.0), 1.0);
+        assert_eq!(
+            histogram,
+            RateHistogram {
+                start: 0.0,
+                width: 1.0,
+                counts: vec![2, 3, 0, 0, 1, 0],
+            }
+        );
+        assert_eq!(histogram.bucket(1.5), Some(1));
+        assert_eq!(histogram.bucket(6.5), None);
+    }
+
+    #[test]
+    fn test_elapsed_histogram() {
+        let elapsed = vec![0.0, 1.0, 10.0, 100.0, 1000.0, 0.0, 30.0];
+        let histogram = ElapsedHistogram::new(&elapsed, 3);
+        assert_eq!(
+            histogram,
+            ElapsedHistogram {
+                log_bounds: (0.0, 3.0),
+                counts: vec![1, 2, 2],
+                zeros: 2,
+            }
+        );
+        assert_eq!(histogram.bucket(0.0), None);
+        assert_eq!(histogram.bucket(5.0), Some(0));
+    }
+
+    #[test]
+    fn rate_state_zoom() {
+        let mut state = RateState::new((0.0, 90.0));
+        state.zoom_in(30.0);
+        assert_eq!(state.interval, (20.0, 50.0));
+        state.zoom_in(35.0);
+        assert_eq!(state.interval, (30.0, 40.0));
+        state.zoom_out(35.0);
+        assert_eq!(state.interval, (20.0, 50.0));
+        state.zoom_out(0.0);
+        assert_eq!(state.interval, (0.0, 90.0));
+    }
+}
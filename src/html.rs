@@ -0,0 +1,225 @@
+//! Writes a self-contained HTML report of a log's elapsed times, which can be
+//! viewed in a browser without installing anything
+
+use crate::app::{render_seconds, App};
+use crate::histogram::ElapsedHistogram;
+use crate::report::largest_elapsed;
+use crate::svg::{bar_chart, escape, line_chart};
+use chrono::Duration;
+use serde_json::json;
+use std::io::Write;
+
+const CHART_WIDTH: usize = 800;
+const CHART_HEIGHT: usize = 250;
+const HISTOGRAM_BUCKETS: usize = 50;
+const TABLE_ROWS: usize = 100;
+
+const STYLE: &str = r#"
+body { font-family: sans-serif; background: #282a36; color: #f8f8f2; margin: 2em; }
+h1, h2 { font-weight: normal; }
+a { color: #8be9fd; }
+svg { background: #21222c; display: block; }
+.line { stroke: #8be9fd; stroke-width: 1.5; }
+.bar { fill: #ffb86c; }
+.axis { display: flex; justify-content: space-between; width: 800px; font-size: small; }
+table { border-collapse: collapse; font-family: monospace; }
+td, th { padding: 0 1em; text-align: left; white-space: pre; }
+#log { height: 400px; overflow-y: scroll; position: relative; background: #21222c; font-family: monospace; }
+#log-rows { position: absolute; left: 0; right: 0; }
+.row { height: 18px; line-height: 18px; white-space: pre; }
+.row.selected { background: #44475a; }
+.number, .elapsed { display: inline-block; color: #6272a4; padding-right: 1em; text-align: right; }
+"#;
+
+// Renders only the visible rows of the log, so that large logs remain responsive
+const SCRIPT: &str = r#"
+const data = JSON.parse(document.getElementById('log-data').textContent);
+const ROW_HEIGHT = 18;
+const view = document.getElementById('log');
+const rows = document.getElementById('log-rows');
+const numberWidth = String(data.lines.length).length + 1;
+let selected = null;
+document.getElementById('log-spacer').style.height = data.lines.length * ROW_HEIGHT + 'px';
+
+function cell(className, text, width) {
+  const span = document.createElement('span');
+  span.className = className;
+  span.style.width = width + 'ch';
+  span.textContent = text;
+  return span;
+}
+
+function render() {
+  const first = Math.floor(view.scrollTop / ROW_HEIGHT);
+  const last = Math.min(first + Math.ceil(view.clientHeight / ROW_HEIGHT) + 1, data.lines.length);
+  rows.style.top = first * ROW_HEIGHT + 'px';
+  rows.textContent = '';
+  for (let i = first; i < last; i++) {
+    const row = document.createElement('div');
+    row.className = i === selected ? 'row selected' : 'row';
+    row.appendChild(cell('number', i, numberWidth));
+    row.appendChild(cell('elapsed', data.elapsed[i], 12));
+    row.appendChild(document.createTextNode(data.lines[i]));
+    rows.appendChild(row);
+  }
+}
+
+function jump(line) {
+  selected = line;
+  view.scrollTop = Math.max(0, line - 3) * ROW_HEIGHT;
+  render();
+  view.scrollIntoView({ behavior: 'smooth' });
+}
+
+document.querySelectorAll('[data-line]').forEach(link => {
+  link.addEventListener('click', event => {
+    event.preventDefault();
+    jump(Number(link.dataset.line));
+  });
+});
+
+document.querySelector('#chart svg').addEventListener('click', event => {
+  const bounds = event.currentTarget.getBoundingClientRect();
+  const position = (event.clientX - bounds.left) / bounds.width;
+  jump(Math.round(position * (data.lines.length - 1)));
+});
+
+view.addEventListener('scroll', render);
+render();
+"#;
+
+// Labels for the edges of a logarithmic axis, given base 10 logarithms of milliseconds
+fn log_axis_labels(log_bounds: (f64, f64)) -> (String, String) {
+    let label = |log_millis: f64| {
+        render_seconds(Duration::milliseconds(10f64.powf(log_millis).round() as i64))
+    };
+    (label(log_bounds.0), label(log_bounds.1))
+}
+
+/// Writes an HTML page containing the cumulative elapsed time chart, a histogram of
+/// elapsed times, a table of the largest elapsed times and a scrollable view of the log.
+/// Clicking on the chart or a table row scrolls the log view to that line.
+pub fn write_html_report<W: Write + ?Sized>(app: &App, out: &mut W) -> Result<(), failure::Error> {
+    let num_lines = app.lines.len();
+    let duration = app.lines[num_lines - 1].timestamp - app.lines[0].timestamp;
+    let percentiles = app.elapsed_percentiles;
+
+    let mut chart_state = app.chart_state.clone();
+    chart_state.reset_zoom();
+    chart_state.set_horizontal_resolution(CHART_WIDTH);
+    let section = chart_state.section();
+    let chart = line_chart(
+        &section.points,
+        (0.0, (num_lines - 1) as f64),
+        (0.0, section.y_bounds.1),
+        CHART_WIDTH,
+        CHART_HEIGHT,
+    );
+
+    let elapsed_millis: Vec<_> = app.lines.iter().map(|l| l.elapsed_millis).collect();
+    let histogram = ElapsedHistogram::new(&elapsed_millis, HISTOGRAM_BUCKETS);
+    let (histogram_min, histogram_max) = log_axis_labels(histogram.log_bounds);
+
+    write!(
+        out,
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Lag report</title>
+<style>{style}</style>
+</head>
+<body>
+<h1>Lag report</h1>
+<p>{num_lines} lines over {duration}. Elapsed time percentiles: p50 {p50}, p90 {p90}, p99 {p99}, max {max}.</p>
+<h2>Cumulative elapsed time</h2>
+<div id="chart">{chart}</div>
+<div class="axis"><span>0</span><span>Line number</span><span>{last_line}</span></div>
+<h2>Elapsed time distribution</h2>
+{histogram}
+<div class="axis"><span>{histogram_min}</span><span>Elapsed time (log scale, {zeros} zero elapsed times not shown)</span><span>{histogram_max}</span></div>
+<h2>Largest elapsed times</h2>
+<table>
+<tr><th>Line</th><th>Elapsed</th><th>Text</th></tr>
+"#,
+        style = STYLE,
+        num_lines = num_lines,
+        duration = render_seconds(duration),
+        p50 = render_seconds(percentiles.p50),
+        p90 = render_seconds(percentiles.p90),
+        p99 = render_seconds(percentiles.p99),
+        max = render_seconds(percentiles.max),
+        chart = chart,
+        last_line = num_lines - 1,
+        histogram = bar_chart(&histogram.counts, CHART_WIDTH, CHART_HEIGHT),
+        histogram_min = histogram_min,
+        histogram_max = histogram_max,
+        zeros = histogram.zeros,
+    )?;
+
+    for line in largest_elapsed(app, TABLE_ROWS) {
+        writeln!(
+            out,
+            r##"<tr><td><a href="#" data-line="{n}">{n}</a></td><td>{elapsed}</td><td>{text}</td></tr>"##,
+            n = line.line_number,
+            elapsed = render_seconds(line.elapsed),
+            text = escape(line.line)
+        )?;
+    }
+
+    let data = json!({
+        "lines": app.lines.iter().map(|l| l.line).collect::<Vec<_>>(),
+        "elapsed": app.lines.iter().map(|l| render_seconds(l.elapsed)).collect::<Vec<_>>(),
+    });
+    // A "</" in the data would otherwise end the script element early
+    let data = serde_json::to_string(&data)?.replace("</", "<\\/");
+
+    write!(
+        out,
+        r#"</table>
+<h2>Log</h2>
+<div id="log"><div id="log-spacer"></div><div id="log-rows"></div></div>
+<script type="application/json" id="log-data">{data}</script>
+<script>{script}</script>
+</body>
+</html>
+"#,
+        data = data,
+        script = SCRIPT,
+    )?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::tests::log_lines;
+    use crate::app::Settings;
+
+    #[test]
+    fn test_write_html_report() {
+        let lines = log_lines(&[
+            (0, "start"),
+            (1000, "<b>bold</b> & </script>"),
+            (5000, "done"),
+        ]);
+        let app = App::new(&lines, &Settings::default());
+
+        let mut out = Vec::new();
+        write_html_report(&app, &mut out).unwrap();
+        let html = String::from_utf8(out).unwrap();
+
+        assert!(html.contains(
+            "<p>3 lines over 5.000s. Elapsed time percentiles: p50 1.000s, p90 4.000s, p99 4.000s, max 4.000s.</p>"
+        ));
+        assert!(html.contains("<span>1.000s</span><span>Elapsed time (log scale"));
+        assert!(html.contains(
+            r##"<tr><td><a href="#" data-line="2">2</a></td><td>4.000s</td><td>2020-02-29 10:00:05.000Z done</td></tr>"##
+        ));
+        assert!(html.contains(r#""elapsed":["0.000s","1.000s","4.000s"]"#));
+        assert!(html.contains("&lt;b&gt;bold&lt;/b&gt; &amp; &lt;/script&gt;"));
+        assert!(html.contains(r#"<b>bold<\/b> & <\/script>"#));
+        assert_eq!(html.matches("</script>").count(), 2);
+    }
+}
@@ -0,0 +1,110 @@
+//! Extracts keys such as thread names or request IDs from log lines, so that elapsed
+//! times can be computed between lines with the same key
+
+use crate::spans::KEY_GROUP;
+use rayon::prelude::*;
+use regex::Regex;
+use serde_json::Value;
+use std::borrow::Cow;
+use std::collections::BTreeSet;
+
+#[derive(Debug, Clone)]
+pub enum KeyExtractor {
+    /// Uses the capture group named `key` if present, and otherwise the first
+    /// capture group or the entire match
+    Regex(Regex),
+    /// Parses the log line from its first `{` onwards as a JSON object and reads
+    /// the given field. Nested fields can be accessed using `.` as a separator.
+    JsonField(String),
+}
+
+impl KeyExtractor {
+    pub fn extract<'a>(&self, line: &'a str) -> Option<Cow<'a, str>> {
+        match self {
+            KeyExtractor::Regex(regex) => {
+                let captures = regex.captures(line)?;
+                let m = captures
+                    .name(KEY_GROUP)
+                    .or_else(|| captures.get(1))
+                    .or_else(|| captures.get(0))?;
+                Some(Cow::Borrowed(m.as_str()))
+            }
+            KeyExtractor::JsonField(field) => {
+                let start = line.find('{')?;
+                let value: Value = serde_json::from_str(&line[start..]).ok()?;
+                let pointer = format!("/{}", field.replace('.', "/"));
+                match value.pointer(&pointer)? {
+                    Value::Null => None,
+                    Value::String(s) => Some(Cow::Owned(s.clone())),
+                    v => Some(Cow::Owned(v.to_string())),
+                }
+            }
+        }
+    }
+}
+
+/// The distinct keys found in a log, and the key for each line.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Keys {
+    /// Distinct keys in sorted order
+    pub names: Vec<String>,
+    /// Index into `names` for each log line, or `None` if no key was found
+    pub ids: Vec<Option<usize>>,
+}
+
+#[inline(never)]
+pub fn extract_keys(extractor: &KeyExtractor, lines: &[&str]) -> Keys {
+    let keys: Vec<_> = lines.par_iter().map(|l| extractor.extract(l)).collect();
+
+    let names: Vec<String> = keys
+        .iter()
+        .filter_map(|k| k.as_ref())
+        .map(|k| k.as_ref())
+        .collect::<BTreeSet<&str>>()
+        .into_iter()
+        .map(|k| k.to_string())
+        .collect();
+
+    let ids = keys
+        .iter()
+        .map(|k| {
+            k.as_ref()
+                .map(|k| names.binary_search_by(|n| n.as_str().cmp(k)).unwrap())
+        })
+        .collect();
+
+    Keys { names, ids }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extract_keys_with_regex() {
+        let lines = vec![
+            "12:00 [worker-2] starting",
+            "12:01 [worker-1] starting",
+            "12:02 no thread here",
+            "12:03 [worker-2] done",
+        ];
+        let extractor = KeyExtractor::Regex(Regex::new(r"\[(worker-\d+)\]").unwrap());
+        assert_eq!(
+            extract_keys(&extractor, &lines),
+            Keys {
+                names: vec!["worker-1".into(), "worker-2".into()],
+                ids: vec![Some(1), Some(0), None, Some(1)],
+            }
+        );
+    }
+
+    #[test]
+    fn extract_key_from_json_field() {
+        let extractor = KeyExtractor::JsonField("ctx.request".into());
+        let line = r#"2020-01-01T00:00:00Z {"msg": "hi", "ctx": {"request": 17}}"#;
+        assert_eq!(extractor.extract(line), Some(Cow::Borrowed("17")));
+        let line = r#"2020-01-01T00:00:00Z {"msg": "hi", "ctx": {"request": "abc"}}"#;
+        assert_eq!(extractor.extract(line), Some(Cow::Borrowed("abc")));
+        assert_eq!(extractor.extract("not json"), None);
+    }
+}
@@ -1,9 +1,16 @@
+use chrono::format::{Item, StrftimeItems};
 use chrono::Utc;
 use crossterm::{
-    event::{self, Event as CEvent, KeyCode},
+    event::{
+        self, DisableMouseCapture, EnableMouseCapture, Event as CEvent, KeyCode, MouseButton,
+        MouseEvent,
+    },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
+use failure::err_msg;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
 use std::fs::{read_to_string, File};
 use std::io::Write;
 use std::path::PathBuf;
@@ -13,14 +20,47 @@ use structopt::StructOpt;
 use tui::{backend::CrosstermBackend, Terminal};
 
 mod app;
-use app::App;
+use app::{App, Attribution, DiffListOptions, DiffOrder, DiffSelection, Settings};
+mod bars;
+mod baseline;
+use baseline::{check, write_regressions, Baseline, Thresholds};
+mod bindings;
+use bindings::KeyBindings;
 mod chart;
+mod compare;
+mod config;
+use compare::{align, comparison_svg, write_comparison, Alignment};
+use config::Config;
 mod cursor;
+mod export;
+use export::{export, ExportFilter, ExportFormat};
 mod gaugagraph;
 mod generate;
 use generate::generate_log;
+mod histogram;
+mod html;
+use html::write_html_report;
+mod keys;
+use keys::KeyExtractor;
 mod render;
-use render::draw;
+use render::{chart_svg, draw};
+mod report;
+use report::write_report;
+mod spans;
+use spans::SpanRule;
+mod stats;
+use stats::parse_duration;
+mod svg;
+mod templates;
+mod theme;
+use theme::Theme;
+mod trace;
+use trace::write_trace;
+
+// Value names for --span and --range, as constants because older versions of structopt only
+// accept paths and literals in attributes
+const SPAN_VALUE_NAMES: &[&str] = &["START", "END"];
+const RANGE_VALUE_NAMES: &[&str] = &["FIRST", "LAST"];
 
 #[derive(Debug, StructOpt)]
 #[structopt(name = "Lag", about = "A TUI for viewing elapsed times in log files")]
@@ -40,6 +80,176 @@ struct Opt {
     /// If true then a randomly generated input file is used.
     #[structopt(long, short)]
     generate: bool,
+
+    /// Pairs lines matching the START regex with the next line matching the END regex and
+    /// shows the resulting spans in the spans panel. If both regexes contain a capture group
+    /// named "key" then lines are only paired if the captured values are equal.
+    /// Can be provided multiple times.
+    #[structopt(long, number_of_values = 2, value_names = SPAN_VALUE_NAMES)]
+    span: Vec<String>,
+
+    /// Computes elapsed times relative to the previous line with the same key, e.g. a thread
+    /// name, extracted using this regex. Uses the capture group named "key" if present,
+    /// and otherwise the first capture group.
+    #[structopt(long)]
+    key_regex: Option<String>,
+
+    /// Computes elapsed times relative to the previous line with the same key, read from this
+    /// field of a JSON object within each line. Nested fields are separated by '.'.
+    #[structopt(long)]
+    key_field: Option<String>,
+
+    /// Whether the time between two lines is attributed to the line after the gap ("following")
+    /// or before it ("preceding"). Can be toggled from within the app.
+    #[structopt(long, default_value = "following")]
+    attribute_to: Attribution,
+
+    /// The number of lines to show in the largest diffs panel.
+    #[structopt(long, default_value = "1000")]
+    diff_count: usize,
+
+    /// The order of lines in the largest diffs panel: "elapsed", "line" or "time".
+    #[structopt(long, default_value = "elapsed")]
+    diff_order: DiffOrder,
+
+    /// If true then the diffs panel shows the smallest non-zero elapsed times rather than the largest.
+    #[structopt(long)]
+    smallest_diffs: bool,
+
+    /// The maximum number of entries from the largest diffs panel to mark on the chart.
+    #[structopt(long, default_value = "10")]
+    chart_markers: usize,
+
+    /// Where to save the chart when pressing 'e' in the chart panel.
+    #[structopt(long, default_value = "lag-chart.svg", parse(from_os_str))]
+    chart_svg: PathBuf,
+
+    /// Where to save the lines selected or visible on the chart when pressing 'E', as CSV,
+    /// or as JSON lines if the path ends in ".jsonl". Only lines with the key selected in
+    /// the largest diffs panel are saved.
+    #[structopt(long, default_value = "lag-lines.csv", parse(from_os_str))]
+    export_lines: PathBuf,
+
+    /// Highlights lines whose elapsed time is above this threshold, e.g. "5s" or "250ms",
+    /// and allows jumping between them with 'n' and 'N'.
+    #[structopt(long, parse(try_from_str = parse_duration))]
+    gap: Option<chrono::Duration>,
+
+    /// The format of the timestamp column in the log panel, toggled with 'd', using chrono's
+    /// strftime syntax. Defaults to "%Y-%m-%d %H:%M:%S%.3f".
+    #[structopt(long)]
+    timestamp_format: Option<String>,
+
+    #[structopt(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, StructOpt)]
+enum Command {
+    /// Prints a summary of the log's elapsed times to stdout instead of starting the TUI.
+    Report {
+        /// The number of largest elapsed times to list.
+        #[structopt(long, default_value = "10")]
+        top: usize,
+    },
+    /// Writes the line number, timestamp, elapsed time, key and text of each line
+    /// as CSV or JSON lines instead of starting the TUI.
+    Export {
+        /// "csv" or "jsonl".
+        #[structopt(long, default_value = "csv")]
+        format: ExportFormat,
+
+        /// File to write to. Defaults to stdout.
+        #[structopt(short, long, parse(from_os_str))]
+        output: Option<PathBuf>,
+
+        /// Only exports lines with line numbers from FIRST to LAST inclusive.
+        #[structopt(long, number_of_values = 2, value_names = RANGE_VALUE_NAMES)]
+        range: Vec<usize>,
+
+        /// Only exports lines with this key. Requires --key-regex or --key-field.
+        #[structopt(long)]
+        key: Option<String>,
+    },
+    /// Writes the log's timing in the Chrome Trace Event format, for viewing in Perfetto
+    /// or chrome://tracing, instead of starting the TUI. Each line becomes an event covering
+    /// the time attributed to it, on a track per key, and each span becomes an event on a
+    /// track per span rule and key.
+    Trace {
+        /// File to write to. Defaults to stdout.
+        #[structopt(short, long, parse(from_os_str))]
+        output: Option<PathBuf>,
+    },
+    /// Writes a self-contained HTML report with the cumulative elapsed time chart, elapsed
+    /// time histogram, largest elapsed times and a scrollable view of the log, instead of
+    /// starting the TUI.
+    Html {
+        /// File to write to. Defaults to stdout.
+        #[structopt(short, long, parse(from_os_str))]
+        output: Option<PathBuf>,
+    },
+    /// Aligns two runs of the same job and compares the time taken to reach each aligned
+    /// line, instead of starting the TUI. Lines are aligned by template unless --anchor is
+    /// provided. The nth line with a template or matching an anchor in the first log is aligned
+    /// with the nth such line in the second log.
+    Diff {
+        #[structopt(parse(from_os_str))]
+        first: PathBuf,
+
+        #[structopt(parse(from_os_str))]
+        second: PathBuf,
+
+        /// Only aligns lines matching this regex. Can be provided multiple times.
+        #[structopt(long)]
+        anchor: Vec<String>,
+
+        /// The number of steps with the largest increase in elapsed time to list.
+        #[structopt(long, default_value = "20")]
+        top: usize,
+
+        /// If set then the cumulative elapsed time in each log is plotted to this SVG file.
+        #[structopt(long, parse(from_os_str))]
+        svg: Option<PathBuf>,
+    },
+    /// Writes the count and the total, p50, p99 and maximum elapsed times of the lines
+    /// matching each template as JSON, for use as a baseline by the check subcommand.
+    Baseline {
+        /// File to write to. Defaults to stdout.
+        #[structopt(short, long, parse(from_os_str))]
+        output: Option<PathBuf>,
+    },
+    /// Compares the total and p99 elapsed time of each template with a baseline written by
+    /// the baseline subcommand, and lists the templates which got slower. Exits with status 1
+    /// if there are any, so that it can be used as a CI check.
+    Check {
+        /// Baseline file written by the baseline subcommand.
+        #[structopt(parse(from_os_str))]
+        baseline: PathBuf,
+
+        /// Allowed increase as a percentage of the baseline value.
+        #[structopt(long, default_value = "20")]
+        tolerance: f64,
+
+        /// Allowed absolute increase in milliseconds. Increases are only regressions if they
+        /// exceed both this and --tolerance.
+        #[structopt(long, default_value = "100")]
+        min_increase_ms: i64,
+    },
+    /// Writes the chart of cumulative elapsed time against line number, with markers for the
+    /// largest elapsed times, as an SVG image instead of starting the TUI.
+    Svg {
+        /// File to write to. Defaults to stdout.
+        #[structopt(short, long, parse(from_os_str))]
+        output: Option<PathBuf>,
+
+        /// Image width in pixels.
+        #[structopt(long, default_value = "1200")]
+        width: usize,
+
+        /// Image height in pixels.
+        #[structopt(long, default_value = "600")]
+        height: usize,
+    },
 }
 
 pub enum Event<I> {
@@ -47,6 +257,79 @@ pub enum Event<I> {
     Tick,
 }
 
+/// A user input, as recorded in the action log. Action logs written before mouse
+/// support was added contain only key codes, which deserialise as `Action::Key`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Action {
+    Key(KeyCode),
+    Mouse(MouseEvent),
+}
+
+fn settings(opt: &Opt) -> Result<Settings, failure::Error> {
+    let span_rules = opt
+        .span
+        .chunks(2)
+        .map(|r| SpanRule::new(&r[0], &r[1]))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let key_extractor = match (&opt.key_regex, &opt.key_field) {
+        (Some(_), Some(_)) => {
+            return Err(err_msg(
+                "At most one of --key-regex and --key-field can be provided",
+            ))
+        }
+        (Some(regex), None) => Some(KeyExtractor::Regex(Regex::new(regex)?)),
+        (None, Some(field)) => Some(KeyExtractor::JsonField(field.clone())),
+        (None, None) => None,
+    };
+
+    if let Some(format) = &opt.timestamp_format {
+        if StrftimeItems::new(format).any(|item| item == Item::Error) {
+            return Err(failure::format_err!(
+                "Invalid --timestamp-format '{}'",
+                format
+            ));
+        }
+    }
+
+    Ok(Settings {
+        span_rules,
+        key_extractor,
+        attribution: opt.attribute_to,
+        diff_list: DiffListOptions {
+            count: opt.diff_count,
+            selection: if opt.smallest_diffs {
+                DiffSelection::SmallestNonZero
+            } else {
+                DiffSelection::Largest
+            },
+            order: opt.diff_order,
+        },
+        chart_markers: opt.chart_markers,
+        chart_svg_path: opt.chart_svg.clone(),
+        export_path: opt.export_lines.clone(),
+        gap_threshold: opt.gap,
+        timestamp_format: opt.timestamp_format.clone(),
+        ..Settings::default()
+    })
+}
+
+// Calls `write` with the file at `output` if provided, and otherwise with stdout
+fn with_output<F>(output: Option<PathBuf>, write: F) -> Result<(), failure::Error>
+where
+    F: FnOnce(&mut dyn Write) -> Result<(), failure::Error>,
+{
+    match output {
+        Some(path) => write(&mut File::create(path)?),
+        None => {
+            let stdout = std::io::stdout();
+            let mut out = stdout.lock();
+            write(&mut out)
+        }
+    }
+}
+
 #[inline(never)]
 fn read_log(path: &PathBuf) -> Result<String, failure::Error> {
     std::fs::read_to_string(&path).map_err(|e| e.into())
@@ -54,6 +337,39 @@ fn read_log(path: &PathBuf) -> Result<String, failure::Error> {
 
 fn main() -> Result<(), failure::Error> {
     let opt = Opt::from_args();
+    let settings = settings(&opt)?;
+
+    if let Some(Command::Diff {
+        first,
+        second,
+        anchor,
+        top,
+        svg,
+    }) = &opt.command
+    {
+        let alignment = if anchor.is_empty() {
+            Alignment::Templates
+        } else {
+            Alignment::Anchors(
+                anchor
+                    .iter()
+                    .map(|a| Regex::new(a))
+                    .collect::<Result<_, _>>()?,
+            )
+        };
+        let (first_log, second_log) = (read_log(first)?, read_log(second)?);
+        let first_lines: Vec<_> = first_log.lines().collect();
+        let second_lines: Vec<_> = second_log.lines().collect();
+        let first = App::new(&first_lines, &settings);
+        let second = App::new(&second_lines, &settings);
+
+        let steps = align(&first, &second, &alignment);
+        if let Some(path) = svg {
+            std::fs::write(path, comparison_svg(&steps, 1200, 600))?;
+        }
+        let stdout = std::io::stdout();
+        return write_comparison(&first, &second, &steps, *top, &mut stdout.lock());
+    }
 
     let log_file = if opt.generate {
         generate_log("gen_log.txt", Utc::now(), 750_000);
@@ -63,28 +379,118 @@ fn main() -> Result<(), failure::Error> {
     };
     let log = read_log(&log_file)?;
 
+    match opt.command {
+        Some(Command::Report { top }) => {
+            let lines: Vec<_> = log.lines().collect();
+            let app = App::new(&lines, &settings);
+            let stdout = std::io::stdout();
+            write_report(&app, top, &mut stdout.lock())?;
+            return Ok(());
+        }
+        Some(Command::Export {
+            format,
+            output,
+            range,
+            key,
+        }) => {
+            let range = match range.as_slice() {
+                [] => None,
+                [first, last] if first <= last => Some((*first, *last)),
+                _ => return Err(err_msg("--range FIRST must not be greater than LAST")),
+            };
+            let lines: Vec<_> = log.lines().collect();
+            let app = App::new(&lines, &settings);
+            let filter = ExportFilter { range, key };
+            return with_output(output, |out| export(&app, &filter, format, out));
+        }
+        Some(Command::Trace { output }) => {
+            let lines: Vec<_> = log.lines().collect();
+            let app = App::new(&lines, &settings);
+            return with_output(output, |out| write_trace(&app, out));
+        }
+        Some(Command::Html { output }) => {
+            let lines: Vec<_> = log.lines().collect();
+            let app = App::new(&lines, &settings);
+            return with_output(output, |out| write_html_report(&app, out));
+        }
+        Some(Command::Svg {
+            output,
+            width,
+            height,
+        }) => {
+            let lines: Vec<_> = log.lines().collect();
+            let mut app = App::new(&lines, &settings);
+            app.chart_state.set_horizontal_resolution(width);
+            let svg = chart_svg(&app, width, height);
+            return with_output(output, |out| Ok(out.write_all(svg.as_bytes())?));
+        }
+        Some(Command::Baseline { output }) => {
+            let lines: Vec<_> = log.lines().collect();
+            let app = App::new(&lines, &settings);
+            let baseline = Baseline::new(&app);
+            return with_output(output, |out| {
+                Ok(serde_json::to_writer_pretty(out, &baseline)?)
+            });
+        }
+        Some(Command::Check {
+            baseline,
+            tolerance,
+            min_increase_ms,
+        }) => {
+            let baseline: Baseline = serde_json::from_str(&read_to_string(baseline)?)?;
+            let lines: Vec<_> = log.lines().collect();
+            let app = App::new(&lines, &settings);
+            let thresholds = Thresholds {
+                percent: tolerance,
+                min_increase: chrono::Duration::milliseconds(min_increase_ms),
+            };
+            let regressions = check(&baseline, &Baseline::new(&app), thresholds);
+            let stdout = std::io::stdout();
+            write_regressions(&regressions, &mut stdout.lock())?;
+            if !regressions.is_empty() {
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        Some(Command::Diff { .. }) | None => {}
+    }
+
+    // The config file only affects the TUI, so it isn't read for the subcommands
+    let config = Config::load()?;
+    let settings = Settings {
+        layout: config.layout,
+        theme: Theme::from_env(config.theme),
+        bindings: KeyBindings::new(&config.keys)?,
+        ..settings
+    };
+
     enable_raw_mode()?;
     let mut stdout = std::io::stdout();
-    execute!(stdout, EnterAlternateScreen)?;
+    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
     terminal.hide_cursor()?;
     terminal.clear()?;
 
     let lines: Vec<_> = log.lines().collect();
-    let mut app = App::new(&lines);
+    let mut app = App::new(&lines, &settings);
 
     if let Some(file) = opt.read_actions {
         let actions = read_action_log(&file)?;
-        for key in &actions {
+        for action in &actions {
             draw(&mut terminal, &mut app)?;
-            if handle_key(*key, &mut app) {
-                disable_raw_mode()?;
-                execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
-                terminal.show_cursor()?;
+            if handle_action(*action, &mut app) {
                 break;
             }
         }
+        // Action logs may end without quitting, e.g. if quitting needed confirming
+        disable_raw_mode()?;
+        execute!(
+            terminal.backend_mut(),
+            LeaveAlternateScreen,
+            DisableMouseCapture
+        )?;
+        terminal.show_cursor()?;
     } else {
         let mut actions = Vec::new();
         let (tx, rx) = mpsc::channel();
@@ -92,8 +498,12 @@ fn main() -> Result<(), failure::Error> {
             loop {
                 // Poll for tick rate duration. If no events then send tick event.
                 if event::poll(std::time::Duration::from_millis(250)).unwrap() {
-                    if let CEvent::Key(key) = event::read().unwrap() {
-                        tx.send(Event::Input(key)).unwrap();
+                    match event::read().unwrap() {
+                        CEvent::Key(key) => tx.send(Event::Input(Action::Key(key.code))).unwrap(),
+                        CEvent::Mouse(mouse) => {
+                            tx.send(Event::Input(Action::Mouse(mouse))).unwrap()
+                        }
+                        CEvent::Resize(_, _) => {}
                     }
                 }
                 tx.send(Event::Tick).unwrap();
@@ -103,13 +513,17 @@ fn main() -> Result<(), failure::Error> {
         loop {
             draw(&mut terminal, &mut app)?;
             match rx.recv()? {
-                Event::Input(key) => {
+                Event::Input(action) => {
                     if opt.write_actions.is_some() {
-                        actions.push(key.code);
+                        actions.push(action);
                     }
-                    if handle_key(key.code, &mut app) {
+                    if handle_action(action, &mut app) {
                         disable_raw_mode()?;
-                        execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+                        execute!(
+                            terminal.backend_mut(),
+                            LeaveAlternateScreen,
+                            DisableMouseCapture
+                        )?;
                         terminal.show_cursor()?;
                         break;
                     }
@@ -121,40 +535,92 @@ fn main() -> Result<(), failure::Error> {
         if let Some(file) = opt.write_actions {
             write_action_log(&file, &actions)?;
         }
+        if app.layout != config.layout {
+            Config {
+                layout: app.layout,
+                ..config
+            }
+            .save()?;
+        }
     }
 
     Ok(())
 }
 
-fn handle_key(key: KeyCode, app: &mut App) -> bool {
-    match key {
-        KeyCode::Char('q') => return true,
-        KeyCode::Char(c) => app.on_char(c),
-        KeyCode::Down => app.on_down(),
-        KeyCode::Up => app.on_up(),
-        KeyCode::PageDown => app.on_page_down(),
-        KeyCode::PageUp => app.on_page_up(),
-        KeyCode::Home => app.on_home(),
-        KeyCode::End => app.on_end(),
-        KeyCode::Left => app.on_left(),
-        KeyCode::Right => app.on_right(),
-        KeyCode::Tab => app.on_tab(),
-        KeyCode::Enter => app.on_enter(),
-        KeyCode::Esc => app.on_escape(),
+fn handle_action(action: Action, app: &mut App) -> bool {
+    app.status_message = None;
+    // Any other input cancels quitting
+    let quit_pending = std::mem::replace(&mut app.quit_pending, false);
+    match action {
+        Action::Key(key) => handle_key(key, app, quit_pending),
+        Action::Mouse(mouse) => {
+            handle_mouse(mouse, app);
+            false
+        }
+    }
+}
+
+fn handle_mouse(mouse: MouseEvent, app: &mut App) {
+    match mouse {
+        MouseEvent::Down(MouseButton::Left, column, row, _) => app.on_click(column, row),
+        MouseEvent::Drag(MouseButton::Left, column, row, _) => app.on_drag(column, row),
+        MouseEvent::Up(MouseButton::Left, column, row, _) => app.on_release(column, row),
+        MouseEvent::ScrollUp(column, row, _) => app.on_scroll(column, row, -3),
+        MouseEvent::ScrollDown(column, row, _) => app.on_scroll(column, row, 3),
         _ => {}
     }
+}
+
+// Returns true if the app should quit
+fn handle_key(key: KeyCode, app: &mut App, quit_pending: bool) -> bool {
+    match app.bindings.command(key, app.active) {
+        Some(bindings::Command::Quit) if quit_pending || !app.bindings.confirm_quit => return true,
+        Some(bindings::Command::Quit) => {
+            app.quit_pending = true;
+            app.status_message = Some(format!(
+                "Press {} again to quit",
+                app.bindings.describe(bindings::Command::Quit)
+            ));
+        }
+        Some(bindings::Command::ExportChart) => save_chart_svg(app),
+        Some(bindings::Command::ExportLines) => save_lines(app),
+        Some(command) => app.on_command(command),
+        None => {}
+    }
     false
 }
 
-pub fn write_action_log(file: &PathBuf, keys: &[KeyCode]) -> Result<(), failure::Error> {
+fn save_chart_svg(app: &mut App) {
+    let svg = chart_svg(app, 1200, 600);
+    let path = app.chart_svg_path.display();
+    app.status_message = Some(match std::fs::write(&app.chart_svg_path, svg) {
+        Ok(()) => format!("Saved chart to {}", path),
+        Err(e) => format!("Unable to save chart to {}: {}", path, e),
+    });
+}
+
+fn save_lines(app: &mut App) {
+    let filter = ExportFilter::current(app);
+    let format = ExportFormat::from_path(&app.export_path);
+    let result = File::create(&app.export_path)
+        .map_err(failure::Error::from)
+        .and_then(|mut file| export(app, &filter, format, &mut file));
+    let path = app.export_path.display();
+    app.status_message = Some(match result {
+        Ok(()) => format!("Saved lines to {}", path),
+        Err(e) => format!("Unable to save lines to {}: {}", path, e),
+    });
+}
+
+pub fn write_action_log(file: &PathBuf, actions: &[Action]) -> Result<(), failure::Error> {
     let mut file = File::create(file)?;
-    let serialised = serde_json::to_string(keys).unwrap();
+    let serialised = serde_json::to_string(actions).unwrap();
     write!(file, "{}", serialised)?;
     Ok(())
 }
 
-pub fn read_action_log(file: &PathBuf) -> Result<Vec<KeyCode>, failure::Error> {
+pub fn read_action_log(file: &PathBuf) -> Result<Vec<Action>, failure::Error> {
     let contents = read_to_string(file)?;
-    let deserialized: Vec<KeyCode> = serde_json::from_str(&contents)?;
+    let deserialized: Vec<Action> = serde_json::from_str(&contents)?;
     Ok(deserialized)
 }
@@ -1,77 +1,116 @@
-use crate::app::{AnnotatedLine, App, Panel, Status};
-use crate::chart::ChartSection;
+use crate::app::{
+    render_duration, AnnotatedLine, App, Attribution, ChartArea, DiffOrder, DiffSelection, Panel,
+    Status,
+};
+use crate::bars::Bars;
+use crate::chart::{ChartMode, ChartSection};
 use crate::gaugagraph::Gaugagraph;
+use crate::histogram::ElapsedHistogram;
+use crate::spans::Span;
+use crate::svg;
+use crate::templates::TemplateStats;
+use crate::theme::Theme;
 use std::io;
 use std::iter;
 use tui::backend::Backend;
 use tui::layout::{Alignment, Constraint, Direction, Layout, Rect};
-use tui::style::{Color, Modifier, Style};
+use tui::style::{Color, Modifier};
 use tui::widgets::{
     Axis, Block, Borders, Chart, Dataset, Marker, Paragraph, SelectableList, Text, Widget,
 };
 use tui::{Frame, Terminal};
 use HelpText::{Body, Title, Gap};
 
-const FOREGROUND: Color = Color::Rgb(248, 248, 242);
-const BACKGROUND: Color = Color::Rgb(40, 42, 54);
-const RED: Color = Color::Rgb(255, 85, 85);
-const ORANGE: Color = Color::Rgb(255, 184, 108);
-const CYAN: Color = Color::Rgb(139, 233, 253);
-const WHITE: Color = Color::Rgb(255, 255, 255);
-
-fn default_style() -> Style {
-    Style::default().bg(BACKGROUND)
-}
-
 trait BlockStatusExt {
-    fn status(self, status: Status) -> Self;
+    fn status(self, theme: &Theme, status: Status) -> Self;
 }
 
 impl BlockStatusExt for Block<'_> {
-    fn status(self, status: Status) -> Self {
-        match status {
-            Status::Active => self
-                .borders(Borders::TOP)
-                .title_style(default_style().fg(RED))
-                .border_style(default_style().fg(RED)),
-            Status::Inactive => self
-                .borders(Borders::TOP)
-                .title_style(default_style().fg(FOREGROUND))
-                .border_style(default_style()),
-        }
+    fn status(self, theme: &Theme, status: Status) -> Self {
+        self.borders(Borders::TOP)
+            .title_style(theme.status(status))
+            .border_style(theme.status(status))
     }
 }
 
 pub fn draw<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> Result<(), io::Error> {
+    let theme = app.theme;
     terminal.draw(|mut f| {
         let size = f.size();
 
-        Block::default().style(default_style()).render(&mut f, size);
+        Block::default().style(theme.base()).render(&mut f, size);
 
+        // Help | Panels
         let rows = Layout::default()
             .direction(Direction::Vertical)
             .margin(1)
-            .constraints(
-                [
-                    Constraint::Percentage(2),
-                    Constraint::Percentage(68),
-                    Constraint::Percentage(30),
-                ]
-                .as_ref(),
-            )
+            .constraints([Constraint::Percentage(2), Constraint::Percentage(98)].as_ref())
             .split(size);
 
-        draw_help(&mut f, rows[0]);
-        draw_log_panel(&mut f, app, rows[1]);
-        draw_bottom_row(&mut f, app, rows[2]);
+        app.panel_areas.clear();
+        let chart_visible = app.left_panel == Panel::Chart
+            && !app.help_mode
+            && if app.maximised {
+                app.active == Panel::Chart
+            } else {
+                !app.layout.hide_left
+            };
+        if !chart_visible {
+            app.chart_area = None;
+        }
+
+        draw_help(&mut f, app, rows[0]);
+        draw_panels(&mut f, app, rows[1]);
     })?;
     Ok(())
 }
 
-fn draw_help<B: Backend>(frame: &mut Frame<B>, rect: Rect) {
-    Paragraph::new([Text::Raw("(Press 'h' to toggle help)".into())].iter())
+fn draw_panels<B: Backend>(frame: &mut Frame<B>, app: &mut App, rect: Rect) {
+    if app.maximised {
+        match app.active {
+            Panel::Log => draw_log_panel(frame, app, rect),
+            Panel::Chart | Panel::Rate | Panel::Distribution => draw_left_panel(frame, app, rect),
+            Panel::List | Panel::Spans | Panel::Templates => draw_right_panel(frame, app, rect),
+        }
+        return;
+    }
+
+    let layout = app.layout;
+    match (!layout.hide_log, layout.show_bottom_row()) {
+        (true, true) => {
+            // Log panel above the bottom row
+            let rows = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints(
+                    [
+                        Constraint::Percentage(layout.log_height),
+                        Constraint::Percentage(100 - layout.log_height),
+                    ]
+                    .as_ref(),
+                )
+                .split(rect);
+            draw_log_panel(frame, app, rows[0]);
+            draw_bottom_row(frame, app, rows[1]);
+        }
+        (true, false) => draw_log_panel(frame, app, rect),
+        _ => draw_bottom_row(frame, app, rect),
+    }
+}
+
+fn draw_help<B: Backend>(frame: &mut Frame<B>, app: &App, rect: Rect) {
+    let theme = app.theme;
+    if let Some(message) = &app.status_message {
+        Paragraph::new([Text::Styled(message.into(), theme.fg(theme.highlight))].iter())
+            .alignment(Alignment::Left)
+            .style(theme.base())
+            .render(frame, rect);
+    }
+    let help = app
+        .bindings
+        .substitute("(Press '{toggle_help}' to toggle help)");
+    Paragraph::new([Text::Raw(help.into())].iter())
         .alignment(Alignment::Right)
-        .style(default_style())
+        .style(theme.base())
         .render(frame, rect);
 }
 
@@ -81,110 +120,251 @@ enum HelpText {
     Gap(usize),
 }
 
-fn help_text(help_section: &[HelpText]) -> Vec<Text> {
+// Command names in braces within the body text are replaced by the keys bound to them
+fn help_text<'a>(app: &App, help_section: &'a [HelpText]) -> Vec<Text<'a>> {
     help_section
         .iter()
         .map(|s| match s {
-            HelpText::Title(c, t) => Text::Styled(format!("{}\n", t).into(), default_style().fg(*c)),
-            HelpText::Body(b) => Text::Raw(format!("{}\n", b).into()),
+            HelpText::Title(c, t) => Text::Styled(format!("{}\n", t).into(), app.theme.fg(*c)),
+            HelpText::Body(b) => Text::Raw(format!("{}\n", app.bindings.substitute(b)).into()),
             HelpText::Gap(n) => Text::Raw(iter::repeat('\n').take(*n).collect::<String>().into()),
         })
         .collect()
 }
 
 fn draw_log_panel<B: Backend>(frame: &mut Frame<B>, app: &mut App, rect: Rect) {
+    let theme = app.theme;
+    app.panel_areas.push((Panel::Log, rect));
     Block::default()
-        .style(default_style())
-        .status(app.status(Panel::Log))
-        .title(&format!("Log (bars scaled by {:.2})", app.log_bar_zoom))
+        .style(theme.base())
+        .status(&theme, app.status(Panel::Log))
+        .title(&format!(
+            "Log (bars scaled by {:.2}, elapsed times attributed to {} line{}{})",
+            app.log_bar_zoom,
+            match app.attribution {
+                Attribution::Following => "following",
+                Attribution::Preceding => "preceding",
+            },
+            match app.gap_threshold {
+                Some(threshold) => format!(
+                    ", {} gaps above {}",
+                    app.gap_lines.len(),
+                    render_duration(threshold)
+                ),
+                None => String::new(),
+            },
+            match app.reference_line {
+                Some(line) if app.show_relative_times =>
+                    format!(", times relative to line {}", line),
+                _ => String::new(),
+            }
+        ))
         .render(frame, rect);
 
     if app.help_mode {
         let text = vec![
             Gap(1),
-            Body("Press tab to move between panels. The active panel is highlighted in red
+            Body("Press {next_panel} to move between panels. The active panel's title is highlighted
 Navigation instructions are shown within each panel"),
             Gap(2),
-            Title(ORANGE, "Log panel (this one)"),
+            Title(theme.highlight, "Log panel (this one)"),
             Body("Each line from the log file is shown alongside its line number and the elapsed time between it and the previous line
+(or the previous line with the same key, if --key-regex or --key-field is provided)
 Coloured bars are shown behind each log line, whose lengths are proportional to the elapsed times
 The bars are initially scaled so that the bar for the largest elapsed time fills the width of the panel"),
             Gap(1),
-            Title(ORANGE, "Chart panel"),
+            Title(theme.highlight, "Chart panel"),
             Body("This panel plots line numbers against the cumulative elapsed time up to that point, as a fraction of the total time
 The red dot shows the position of the current line, which can be moved by scrolling within this panel"),
             Gap(1),
-            Title(ORANGE, "Largest diffs panel"),
+            Title(theme.highlight, "Log rate panel"),
+            Body("Shown in place of the chart panel. Plots the number of lines logged over time, with periods without any lines highlighted"),
+            Gap(1),
+            Title(theme.highlight, "Elapsed time distribution panel"),
+            Body("Shown in place of the chart panel. A histogram of elapsed times on a logarithmic scale, with markers for the 50th, 90th and 99th percentiles and the maximum"),
+            Gap(1),
+            Title(theme.highlight, "Largest diffs panel"),
             Body("This panel shows the lines with largest elapsed times.
-Hitting enter on a selected line moves the current line to that location"),
+Hitting {select} on a selected line moves the current line to that location"),
+            Gap(1),
+            Title(theme.highlight, "Spans panel"),
+            Body("Shown in place of the largest diffs panel when span rules are provided using --span.
+Lists the time between each line matching a start pattern and the matching end line"),
+            Gap(1),
+            Title(theme.highlight, "Templates panel"),
+            Body("Groups lines by template, i.e. with numbers and IDs masked out, and shows the elapsed time for each template.
+Templates are ordered by the total elapsed time of all matching lines"),
             Gap(2),
-            Title(CYAN, "Navigation"),
+            Title(theme.primary, "Navigation"),
+            Gap(1),
+            Title(theme.emphasis, "Vertical scrolling"),
+            Body("{up}/{down}, {page_up}/{page_down}, {top}/{bottom} for the first and last lines"),
+            Gap(1),
+            Title(theme.emphasis, "Horizontal scrolling"),
+            Body("{left}/{right}, {home}/{end}"),
+            Gap(1),
+            Title(theme.emphasis, "Zoom"),
+            Body("{grow} stretches the bars, {shrink} shrinks them
+{reset} resets the zoom"),
+            Gap(1),
+            Title(theme.emphasis, "Mouse"),
+            Body("Click on a panel to make it active, and use the scroll wheel to scroll the log and list panels"),
+            Gap(1),
+            Title(theme.emphasis, "Attribution"),
+            Body("{toggle_attribution} toggles whether the time between two lines is attributed to the line after the gap or the line before it.
+This affects all panels"),
             Gap(1),
-            Title(WHITE, "Vertical scrolling"),
-            Body("Up/Down, PageUp/PageDown"),
+            Title(theme.emphasis, "Layout"),
+            Body("{shrink_log} and {grow_log} resize the log panel, {shrink_left} and {grow_left} resize the panels below it
+{maximise} maximises the active panel, {hide_panel} hides it and {show_panels} shows all panels again
+The layout is saved when quitting"),
             Gap(1),
-            Title(WHITE, "Horizontal scrolling"),
-            Body("Left/Right, Home/End"),
+            Title(theme.emphasis, "Columns"),
+            Body("{toggle_timestamps} toggles a column showing each line's timestamp, formatted using --timestamp-format
+{toggle_relative_times} toggles a column showing the time since the first line
+{set_reference} measures those times from the current line instead, and pressing it again on the same line goes back to the first line"),
             Gap(1),
-            Title(WHITE, "Zoom"),
-            Body("+ stretches the bars, - shrinks them
-Escape resets the zoom"),
+            Title(theme.emphasis, "Gaps"),
+            Body("Lines with elapsed times above the --gap threshold are highlighted
+{next_gap} jumps to the next such line, and {previous_gap} to the previous one"),
+            Gap(1),
+            Title(theme.emphasis, "Key bindings"),
+            Body("Keys can be changed in the \"keys\" section of $XDG_CONFIG_HOME/lag/config.json
+{quit} quits, and must be pressed twice unless \"confirm_quit\" is false"),
         ];
-        let text = help_text(&text);
+        let text = help_text(app, &text);
 
         Paragraph::new(text.iter())
             .alignment(Alignment::Center)
             .block(
                 Block::default()
-                    .status(app.status(Panel::Log))
+                    .status(&theme, app.status(Panel::Log))
                     .borders(Borders::ALL)
                     .title("Log panel"),
             )
-            .style(default_style())
+            .style(theme.base())
             .render(frame, rect);
 
         return;
     }
 
-    // Line number | Elapsed time | Log line
+    // Line number | Timestamp (optional) | Relative time (optional) | Elapsed time | Log line
+    let last_line = app.lines.len() - 1;
+    let line_number_width = last_line.to_string().len() + 2;
+    let mut constraints = vec![Constraint::Length(line_number_width as u16)];
+    if app.show_timestamps {
+        let width = app.timestamp_string(last_line).chars().count() + 2;
+        constraints.push(Constraint::Length(width as u16));
+    }
+    if app.show_relative_times {
+        // Wide enough for the time furthest from the reference line, with a sign and
+        // millisecond precision. Timestamps needn't increase, so this uses the earliest
+        // and latest times rather than the first and last lines.
+        let (earliest, latest) = app.rate_state.extent;
+        let reference = app.reference_line.map_or(0.0, |r| app.times[r]);
+        let max_seconds = (earliest - reference).abs().max((latest - reference).abs()) as i64;
+        let width = 1 + max_seconds.to_string().len() + ".000S".len() + 2;
+        constraints.push(Constraint::Length(width as u16));
+    }
+    // Wide enough for the largest elapsed time with millisecond precision
+    let max_seconds = app.elapsed_percentiles.max.num_seconds();
+    let elapsed_width = max_seconds.to_string().len() + ".000S".len() + 2;
+    constraints.push(Constraint::Length(elapsed_width as u16));
+    constraints.push(Constraint::Min(0));
+
     let split = Layout::default()
         .direction(Direction::Horizontal)
         .margin(2)
-        .constraints(
-            [
-                Constraint::Percentage(5),
-                Constraint::Percentage(5),
-                Constraint::Percentage(90),
-            ]
-            .as_ref(),
-        )
+        .constraints(constraints)
         .split(rect);
 
-    draw_line_numbers(frame, app, split[0]);
-    draw_elapsed_times(frame, app, split[1]);
-    draw_log_lines(frame, app, split[2]);
+    let mut columns = split.into_iter();
+    draw_line_numbers(frame, app, columns.next().unwrap());
+    if app.show_timestamps {
+        let rect = columns.next().unwrap();
+        draw_log_column(frame, app, rect, |app, l| app.timestamp_string(l));
+    }
+    if app.show_relative_times {
+        let rect = columns.next().unwrap();
+        draw_log_column(frame, app, rect, |app, l| app.relative_time_string(l));
+    }
+    draw_elapsed_times(frame, app, columns.next().unwrap());
+    draw_log_lines(frame, app, columns.next().unwrap());
+}
+
+// Draws a column of text alongside the visible log lines
+fn draw_log_column<B, F>(frame: &mut Frame<B>, app: &App, rect: Rect, text: F)
+where
+    B: Backend,
+    F: Fn(&App, usize) -> String,
+{
+    let theme = app.theme;
+    let scroll = app.vertical_log_scroll();
+    let end = app.lines.len().min(scroll + rect.height as usize);
+    let text: Vec<_> = (scroll..end)
+        .map(|l| Text::Raw(format!("{}\n", text(app, l)).into()))
+        .collect();
+
+    Paragraph::new(text.iter())
+        .alignment(Alignment::Left)
+        .wrap(false)
+        .style(theme.fg(theme.primary))
+        .render(frame, rect);
 }
 
 fn draw_bottom_row<B: Backend>(frame: &mut Frame<B>, app: &mut App, rect: Rect) {
+    let layout = app.layout;
+    if layout.hide_left || layout.hide_right {
+        let rect = Layout::default()
+            .margin(1)
+            .constraints([Constraint::Percentage(100)].as_ref())
+            .split(rect)[0];
+        if layout.hide_left {
+            draw_right_panel(frame, app, rect);
+        } else {
+            draw_left_panel(frame, app, rect);
+        }
+        return;
+    }
+
     // Chart | Spacer | Diff list
     let split = Layout::default()
         .direction(Direction::Horizontal)
         .margin(1)
         .constraints(
             [
-                Constraint::Percentage(37),
+                Constraint::Percentage(layout.left_width),
                 Constraint::Percentage(3),
-                Constraint::Percentage(60),
+                Constraint::Percentage(97 - layout.left_width),
             ]
             .as_ref(),
         )
         .split(rect);
 
-    draw_chart(frame, app, split[0]);
-    draw_diff_list(frame, app, split[2]);
+    draw_left_panel(frame, app, split[0]);
+    draw_right_panel(frame, app, split[2]);
+}
+
+fn draw_left_panel<B: Backend>(frame: &mut Frame<B>, app: &mut App, rect: Rect) {
+    app.panel_areas.push((app.left_panel, rect));
+    match app.left_panel {
+        Panel::Rate => draw_rate_histogram(frame, app, rect),
+        Panel::Distribution => draw_elapsed_histogram(frame, app, rect),
+        _ => draw_chart(frame, app, rect),
+    }
+}
+
+fn draw_right_panel<B: Backend>(frame: &mut Frame<B>, app: &mut App, rect: Rect) {
+    app.panel_areas.push((app.right_panel, rect));
+    match app.right_panel {
+        Panel::Spans => draw_span_list(frame, app, rect),
+        Panel::Templates => draw_template_list(frame, app, rect),
+        _ => draw_diff_list(frame, app, rect),
+    }
 }
 
 fn draw_log_lines<B: Backend>(frame: &mut Frame<B>, app: &mut App, rect: Rect) {
+    let theme = app.theme;
     let scroll = app.vertical_log_scroll();
 
     let log_text: Vec<_> = app
@@ -199,152 +379,245 @@ fn draw_log_lines<B: Backend>(frame: &mut Frame<B>, app: &mut App, rect: Rect) {
         .collect();
 
     let data = app.elapsed_time_ratios(scroll, scroll + rect.height as usize);
+    let highlighted = (scroll..scroll + rect.height as usize)
+        .map(|l| app.is_gap(l))
+        .collect();
 
     Gaugagraph::new(
         log_text,
-        default_style(),
-        default_style().bg(ORANGE).fg(BACKGROUND),
+        theme.base(),
+        theme.fill(theme.highlight),
         data,
         app.log_bar_zoom,
     )
+    .highlight(theme.fg(theme.accent), highlighted)
     .block(
         Block::default()
-            .title_style(default_style().modifier(Modifier::BOLD))
-            .border_style(default_style()),
+            .title_style(theme.base().modifier(Modifier::BOLD))
+            .border_style(theme.base()),
     )
     .render(frame, rect);
 }
 
 fn draw_elapsed_times<B: Backend>(frame: &mut Frame<B>, app: &mut App, rect: Rect) {
+    let theme = app.theme;
     let text: Vec<_> = app
         .lines
         .iter()
         .skip(app.vertical_log_scroll())
         .take(rect.height as usize)
-        .map(|l| Text::Raw(format!("{}\n", l.elapsed_string()).into()))
+        .map(|l| {
+            let text = format!("{}\n", l.elapsed_string());
+            if app.is_gap(l.line_number) {
+                Text::Styled(text.into(), theme.fg(theme.accent))
+            } else {
+                Text::Raw(text.into())
+            }
+        })
         .collect();
 
     Paragraph::new(text.iter())
         .block(
             Block::default()
-                .border_style(default_style())
-                .title_style(default_style().modifier(Modifier::BOLD)),
+                .border_style(theme.base())
+                .title_style(theme.base().modifier(Modifier::BOLD)),
         )
-        .style(default_style().fg(ORANGE))
+        .style(theme.fg(theme.highlight))
         .render(frame, rect);
 }
 
 fn draw_line_numbers<B: Backend>(frame: &mut Frame<B>, app: &mut App, rect: Rect) {
+    let theme = app.theme;
     let text: Vec<_> = app
         .line_numbers()
         .skip(app.vertical_log_scroll())
         .take(rect.height as usize)
-        .map(|x| Text::Raw(format!("{}\n", x).into()))
+        .map(|x| {
+            let text = format!("{}\n", x);
+            if app.is_gap(x) {
+                Text::Styled(text.into(), theme.fg(theme.accent))
+            } else {
+                Text::Raw(text.into())
+            }
+        })
         .collect();
 
     Paragraph::new(text.iter())
         .block(
             Block::default()
-                .border_style(default_style())
-                .title_style(default_style().modifier(Modifier::BOLD)),
+                .border_style(theme.base())
+                .title_style(theme.base().modifier(Modifier::BOLD)),
         )
         .alignment(Alignment::Left)
         .wrap(false)
-        .style(default_style())
+        .style(theme.base())
         .render(frame, rect);
 }
 
 fn draw_chart<B: Backend>(frame: &mut Frame<B>, app: &mut App, rect: Rect) {
+    let theme = app.theme;
     if app.help_mode {
         let text = vec![
-            Title(CYAN, "Navigation"),
+            Title(theme.primary, "Navigation"),
             Gap(1),
-            Title(WHITE, "Increase/decrease current line"),
-            Body("Left/Right, or Home/End for larger steps"),
+            Title(theme.emphasis, "Increase/decrease current line"),
+            Body("{left}/{right}, or {home}/{end} for larger steps"),
             Gap(1),
-            Title(WHITE, "Zoom"),
-            Body("Up/Down to zoom in/out, or PageUp/PageDown for larger steps\nEscape resets the zoom level"),
+            Title(theme.emphasis, "Zoom"),
+            Body("{up}/{down} to zoom in/out, or {page_up}/{page_down} for larger steps\n{reset} resets the zoom level"),
+            Gap(1),
+            Title(theme.emphasis, "Markers"),
+            Body("Orange dots mark the largest diffs in the visible range and a white dot marks the line selected in the largest diffs panel\n{toggle_markers} toggles the orange markers"),
+            Gap(1),
+            Title(theme.emphasis, "Chart mode"),
+            Body("{toggle_chart_mode} toggles between plotting cumulative time against line number\nand plotting line number against time"),
+            Gap(1),
+            Title(theme.emphasis, "Export"),
+            Body("{export_chart} saves the chart as an SVG image, to the path given by --chart-svg\n{export_lines} saves the selected lines, or else the visible lines, to the path given by --export-lines"),
+            Gap(1),
+            Title(theme.emphasis, "Range zoom"),
+            Body("{select_range} starts selecting a range from the current line, and {select_range} again zooms to the selected range\n{reset} cancels the selection, and {back} returns to the previous zoom level"),
+            Gap(1),
+            Title(theme.emphasis, "Mouse"),
+            Body("Click to move the current line, or drag to zoom to a range of lines"),
         ];
-        let text = help_text(&text);
+        let text = help_text(app, &text);
 
         Paragraph::new(text.iter())
             .alignment(Alignment::Center)
             .block(
                 Block::default()
-                    .status(app.status(Panel::Chart))
+                    .status(&theme, app.status(Panel::Chart))
                     .borders(Borders::ALL)
                     .title("Chart panel"),
             )
-            .style(default_style())
+            .style(theme.base())
             .render(frame, rect);
 
         return;
     }
 
+    // Braille markers plot two points per column. The y axis labels are at most as wide
+    // as the largest line number, or four characters for fractions of cumulative time
+    let label_width = app.lines.len().to_string().len().max(4) as u16;
+    let columns = rect.width.saturating_sub(label_width + 2) as usize;
+    app.chart_state.set_horizontal_resolution(2 * columns);
+
     let (lower, upper) = app.chart_state.interval;
-    let ChartSection {
-        points, y_bounds, ..
-    } = app.chart_state.section();
-    let label_step_y = (y_bounds.1 - y_bounds.0) / 4.0;
-
-    let y_labels: Vec<_> = vec![
-        y_bounds.0,
-        y_bounds.0 + label_step_y,
-        y_bounds.0 + 2.0 * label_step_y,
-        y_bounds.0 + 3.0 * label_step_y,
-        y_bounds.0 + 4.0 * label_step_y,
-    ]
-    .iter()
-    .map(|x| format!("{:.2}", x))
-    .collect();
+    let current_line = app.vertical_log_scroll();
+    let ChartAxes {
+        points,
+        x_bounds,
+        y_bounds,
+        x_labels,
+        y_labels,
+    } = chart_axes(app);
+    let loc_data = match app.chart_mode {
+        ChartMode::LineNumber => vec![(current_line as f64, 0.5 * y_bounds.0 + 0.5 * y_bounds.1)],
+        ChartMode::Time => vec![chart_point(app, current_line)],
+    };
 
     let cdf = Dataset::default()
-        .name("CumulativeTime")
+        .name(match app.chart_mode {
+            ChartMode::LineNumber => "CumulativeTime",
+            ChartMode::Time => "LineCount",
+        })
         .marker(Marker::Braille)
-        .style(default_style().fg(CYAN))
+        .style(theme.fg(theme.primary))
         .data(&points);
 
-    let x_labels: Vec<_> = (lower..=upper)
-        .step_by(20 * app.lines_per_pixel())
-        .map(|x| x.to_string())
-        .collect();
-
-    let loc_data = vec![(
-        app.vertical_log_scroll() as f64,
-        0.5 * y_bounds.0 + 0.5 * y_bounds.1,
-    )];
     let location = Dataset::default()
         .name("CurrentLine")
         .marker(Marker::Dot)
-        .style(default_style().fg(RED))
+        .style(theme.fg(theme.accent))
         .data(&loc_data);
 
+    let marker_data = chart_marker_points(app);
+    let markers = Dataset::default()
+        .name("LargestDiffs")
+        .marker(Marker::Dot)
+        .style(theme.fg(theme.highlight))
+        .data(&marker_data);
+
+    let selected_data: Vec<_> = match app.selected_diff_line() {
+        Some(line) if line >= lower && line < upper => vec![chart_point(app, line)],
+        _ => Vec::new(),
+    };
+    let selected = Dataset::default()
+        .name("SelectedDiff")
+        .marker(Marker::Dot)
+        .style(theme.fg(theme.emphasis))
+        .data(&selected_data);
+
+    let selection = app.chart_selection();
+    let selection_data: Vec<_> = match selection {
+        Some((start, end)) => points
+            .iter()
+            .filter(|p| {
+                let line = match app.chart_mode {
+                    ChartMode::LineNumber => p.0,
+                    ChartMode::Time => p.1,
+                } as usize;
+                line >= start && line <= end
+            })
+            .cloned()
+            .collect(),
+        None => Vec::new(),
+    };
+    let selected_range = Dataset::default()
+        .name("Selection")
+        .marker(Marker::Braille)
+        .style(theme.fg(theme.emphasis))
+        .data(&selection_data);
+
     let chart_block = Block::default()
-        .style(default_style())
-        .border_style(default_style());
+        .style(theme.base())
+        .border_style(theme.base());
+
+    // The chart plots to the right of the y axis labels and the axis itself,
+    // and above the x axis and its labels
+    let y_label_width = y_labels.iter().map(|l| l.len()).max().unwrap_or(0) as u16;
+    app.chart_area = Some(ChartArea {
+        plot: Rect::new(
+            rect.x + y_label_width + 2,
+            rect.y,
+            rect.width.saturating_sub(y_label_width + 2),
+            rect.height.saturating_sub(2),
+        ),
+        x_bounds,
+    });
 
     let is_active = app.status(Panel::Chart) == Status::Active;
 
     let styled_axis = |title| {
         Axis::default()
             .title(title)
-            .title_style(default_style())
-            .style(default_style().fg(if is_active { RED } else { FOREGROUND }))
-            .labels_style(default_style().modifier(Modifier::ITALIC))
+            .title_style(theme.base())
+            .style(if is_active {
+                theme.fg(theme.accent)
+            } else {
+                theme.base()
+            })
+            .labels_style(theme.base().modifier(Modifier::ITALIC))
     };
 
-    let (lower, upper) = (lower as f64, upper as f64);
-
-    let y_title = format!(
-        "Fraction of cumulative time (zoom: {:.2})",
-        app.chart_state.current_zoom_level()
-    );
+    let zoom = match selection {
+        Some((start, end)) => format!(
+            "{:.2}, selected lines {} to {}",
+            app.chart_state.current_zoom_level(),
+            start,
+            end
+        ),
+        None => format!("{:.2}", app.chart_state.current_zoom_level()),
+    };
+    let (x_title, y_title) = chart_titles(app);
+    let y_title = format!("{} (zoom: {})", y_title, zoom);
 
     Chart::default()
         .block(chart_block)
         .x_axis(
-            styled_axis("Line number")
-                .bounds([lower, upper])
+            styled_axis(x_title)
+                .bounds([x_bounds.0, x_bounds.1])
                 .labels(&x_labels),
         )
         .y_axis(
@@ -352,50 +625,416 @@ fn draw_chart<B: Backend>(frame: &mut Frame<B>, app: &mut App, rect: Rect) {
                 .bounds([y_bounds.0, y_bounds.1])
                 .labels(&y_labels),
         )
-        .style(default_style())
-        .datasets(&[cdf, location])
+        .style(theme.base())
+        .datasets(&[cdf, selected_range, markers, selected, location])
+        .render(frame, rect);
+}
+
+fn draw_rate_histogram<B: Backend>(frame: &mut Frame<B>, app: &mut App, rect: Rect) {
+    let theme = app.theme;
+    if app.help_mode {
+        let text = vec![
+            Title(theme.primary, "Navigation"),
+            Gap(1),
+            Title(theme.emphasis, "Move current line by one bucket"),
+            Body("{left}/{right}, or {home}/{end} for larger steps"),
+            Gap(1),
+            Title(theme.emphasis, "Zoom"),
+            Body("{up}/{down} to zoom in/out, or {page_up}/{page_down} for larger steps\n{reset} resets the zoom level"),
+        ];
+        let text = help_text(app, &text);
+
+        Paragraph::new(text.iter())
+            .alignment(Alignment::Center)
+            .block(
+                Block::default()
+                    .status(&theme, app.status(Panel::Rate))
+                    .borders(Borders::ALL)
+                    .title("Log rate panel"),
+            )
+            .style(theme.base())
+            .render(frame, rect);
+
+        return;
+    }
+
+    let block = Block::default()
+        .style(theme.base())
+        .status(&theme, app.status(Panel::Rate));
+
+    // Bars | Time labels
+    let split = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(1)].as_ref())
+        .split(block.inner(rect));
+
+    app.rate_state.horizontal_resolution = split[0].width.max(1) as usize;
+    let histogram = app.rate_state.histogram(&app.times);
+    let max_count = histogram.counts.iter().cloned().max().unwrap_or(0).max(1);
+    let ratios = histogram
+        .counts
+        .iter()
+        .map(|c| *c as f64 / max_count as f64)
+        .collect();
+
+    let mut bars = Bars::new(ratios, theme.fg(theme.primary));
+    let mut num_empty = 0;
+    for (i, count) in histogram.counts.iter().enumerate() {
+        if *count == 0 {
+            bars = bars.highlight(i, theme.fill(theme.accent));
+            num_empty += 1;
+        }
+    }
+    if let Some(current) = histogram.bucket(app.current_time()) {
+        bars = bars.highlight(current, theme.fg(theme.highlight));
+    }
+
+    let width = chrono::Duration::milliseconds((histogram.width * 1000.0) as i64);
+    block
+        .title(&format!(
+            "Log rate (lines per {}, {} empty buckets, zoom: {:.2})",
+            render_duration(width),
+            num_empty,
+            app.rate_state.current_zoom_level()
+        ))
+        .render(frame, rect);
+    bars.render(frame, split[0]);
+
+    let labels = time_labels(app, (histogram.start, histogram.end()));
+    let labels = spread_labels(&labels, histogram.counts.len().min(split[1].width as usize));
+    Paragraph::new([Text::Raw(labels.into())].iter())
+        .style(theme.base().modifier(Modifier::ITALIC))
+        .render(frame, split[1]);
+}
+
+fn draw_elapsed_histogram<B: Backend>(frame: &mut Frame<B>, app: &mut App, rect: Rect) {
+    let theme = app.theme;
+    if app.help_mode {
+        let text = vec![
+            Body("Each bar counts the lines with elapsed times in a range of durations.
+The ranges are of equal width on a logarithmic scale, so each bar covers a larger range than the one to its left.
+Lines with zero elapsed time cannot be shown on this scale, so are counted in the panel title instead"),
+            Gap(1),
+            Title(theme.primary, "Markers"),
+            Body("The bars containing the 50th, 90th and 99th percentiles and the maximum are highlighted
+in the colours shown beneath the chart"),
+        ];
+        let text = help_text(app, &text);
+
+        Paragraph::new(text.iter())
+            .alignment(Alignment::Center)
+            .block(
+                Block::default()
+                    .status(&theme, app.status(Panel::Distribution))
+                    .borders(Borders::ALL)
+                    .title("Elapsed time distribution panel"),
+            )
+            .style(theme.base())
+            .render(frame, rect);
+
+        return;
+    }
+
+    let block = Block::default()
+        .style(theme.base())
+        .status(&theme, app.status(Panel::Distribution));
+
+    // Bars | Duration labels | Percentile legend
+    let split = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [
+                Constraint::Min(1),
+                Constraint::Length(1),
+                Constraint::Length(1),
+            ]
+            .as_ref(),
+        )
+        .split(block.inner(rect));
+
+    let elapsed: Vec<_> = app.lines.iter().map(|l| l.elapsed_millis).collect();
+    let histogram = ElapsedHistogram::new(&elapsed, split[0].width as usize);
+    let max_count = histogram.counts.iter().cloned().max().unwrap_or(0).max(1);
+    let ratios = histogram
+        .counts
+        .iter()
+        .map(|c| *c as f64 / max_count as f64)
+        .collect();
+
+    let percentiles = app.elapsed_percentiles;
+    let markers = [
+        ("p50", percentiles.p50, theme.emphasis),
+        ("p90", percentiles.p90, theme.highlight),
+        ("p99", percentiles.p99, theme.accent),
+        ("max", percentiles.max, theme.accent),
+    ];
+
+    let mut bars = Bars::new(ratios, theme.fg(theme.primary));
+    for (_, value, colour) in &markers {
+        if let Some(bucket) = histogram.bucket(value.num_milliseconds() as f64) {
+            bars = bars.highlight(bucket, theme.fg(*colour));
+        }
+    }
+
+    block
+        .title(&format!(
+            "Elapsed time distribution (log scale, {} lines with zero elapsed time)",
+            histogram.zeros
+        ))
         .render(frame, rect);
+    bars.render(frame, split[0]);
+
+    let (lower, upper) = histogram.log_bounds;
+    let labels = axis_labels((lower, upper), |x| {
+        let millis = 10f64.powf(x).round() as i64;
+        render_duration(chrono::Duration::milliseconds(millis))
+    });
+    let labels = spread_labels(&labels, histogram.counts.len());
+    Paragraph::new([Text::Raw(labels.into())].iter())
+        .style(theme.base().modifier(Modifier::ITALIC))
+        .render(frame, split[1]);
+
+    let legend: Vec<_> = markers
+        .iter()
+        .map(|(name, value, colour)| {
+            Text::Styled(
+                format!("{} {}  ", name, render_duration(*value)).into(),
+                theme.fg(*colour),
+            )
+        })
+        .collect();
+    Paragraph::new(legend.iter())
+        .style(theme.base())
+        .render(frame, split[2]);
+}
+
+// Lays out labels evenly across `width` columns, dropping alternate labels until they fit
+fn spread_labels(labels: &[String], width: usize) -> String {
+    let mut labels: Vec<_> = labels.iter().collect();
+    while labels.len() > 2 && labels.iter().map(|l| l.len() + 1).sum::<usize>() > width {
+        labels = labels.into_iter().step_by(2).collect();
+    }
+
+    let mut line = vec![' '; width];
+    let n = labels.len();
+    for (i, label) in labels.iter().enumerate() {
+        let start = if i == 0 {
+            0
+        } else {
+            let centre = i * width.saturating_sub(1) / (n - 1);
+            centre
+                .saturating_sub(label.len() / 2)
+                .min(width.saturating_sub(label.len()))
+        };
+        for (j, c) in label.chars().enumerate() {
+            if start + j < width {
+                line[start + j] = c;
+            }
+        }
+    }
+
+    line.into_iter().collect()
+}
+
+// The points plotted on the chart for its current mode and interval, and the bounds and labels
+// of its axes
+struct ChartAxes {
+    points: Vec<(f64, f64)>,
+    x_bounds: (f64, f64),
+    y_bounds: (f64, f64),
+    x_labels: Vec<String>,
+    y_labels: Vec<String>,
+}
+
+fn chart_axes(app: &App) -> ChartAxes {
+    let (lower, upper) = app.chart_state.interval;
+    let ChartSection {
+        points,
+        x_bounds,
+        y_bounds,
+    } = match app.chart_mode {
+        ChartMode::LineNumber => app.chart_state.section(),
+        ChartMode::Time => app.chart_state.time_section(&app.times),
+    };
+
+    match app.chart_mode {
+        ChartMode::LineNumber => ChartAxes {
+            points,
+            x_bounds: (lower as f64, upper as f64),
+            y_bounds,
+            x_labels: axis_labels((lower as f64, upper as f64), |x| format!("{:.0}", x)),
+            y_labels: axis_labels(y_bounds, |y| format!("{:.2}", y)),
+        },
+        ChartMode::Time => ChartAxes {
+            points,
+            x_bounds,
+            y_bounds,
+            x_labels: time_labels(app, x_bounds),
+            y_labels: axis_labels(y_bounds, |y| format!("{:.0}", y)),
+        },
+    }
+}
+
+// The position of `line` on the chart
+fn chart_point(app: &App, line: usize) -> (f64, f64) {
+    match app.chart_mode {
+        ChartMode::LineNumber => (line as f64, app.chart_state.cumulative_deltas[line]),
+        ChartMode::Time => (app.times[line], line as f64),
+    }
 }
 
-fn render_diff_list_item(line: &AnnotatedLine, offset: usize) -> String {
+// The positions of the largest diffs marked on the chart, if markers are shown
+fn chart_marker_points(app: &App) -> Vec<(f64, f64)> {
+    if !app.show_chart_markers {
+        return Vec::new();
+    }
+    app.chart_marker_lines()
+        .into_iter()
+        .map(|line| chart_point(app, line))
+        .collect()
+}
+
+fn chart_titles(app: &App) -> (&'static str, &'static str) {
+    match app.chart_mode {
+        ChartMode::LineNumber => ("Line number", "Fraction of cumulative time"),
+        ChartMode::Time => ("Time", "Line number"),
+    }
+}
+
+/// Renders the chart as shown in the chart panel, including the current line and the
+/// largest diff markers, as a `width` by `height` SVG image.
+pub fn chart_svg(app: &App, width: usize, height: usize) -> String {
+    let axes = chart_axes(app);
+    let (x_title, y_title) = chart_titles(app);
+    svg::chart(
+        &svg::Chart {
+            series: &[svg::Series {
+                name: x_title,
+                points: &axes.points,
+                colour: svg::CYAN,
+            }],
+            x_bounds: axes.x_bounds,
+            y_bounds: axes.y_bounds,
+            x_labels: &axes.x_labels,
+            y_labels: &axes.y_labels,
+            x_title,
+            y_title,
+            current: Some(chart_point(app, app.vertical_log_scroll())),
+            markers: &chart_marker_points(app),
+        },
+        width,
+        height,
+    )
+}
+
+// Five evenly spaced labels from the lower to the upper bound
+fn axis_labels<F: Fn(f64) -> String>(bounds: (f64, f64), format: F) -> Vec<String> {
+    let step = (bounds.1 - bounds.0) / 4.0;
+    (0..5).map(|i| format(bounds.0 + i as f64 * step)).collect()
+}
+
+// Labels for an axis showing seconds since the first line as clock times
+fn time_labels(app: &App, bounds: (f64, f64)) -> Vec<String> {
+    let start = app.lines[0].timestamp;
+    let length = bounds.1 - bounds.0;
+    let format = if length < 10.0 {
+        "%H:%M:%S%.3f"
+    } else if length < 24.0 * 60.0 * 60.0 {
+        "%H:%M:%S"
+    } else {
+        "%m-%d %H:%M"
+    };
+    axis_labels(bounds, |x| {
+        let time = start + chrono::Duration::milliseconds((x * 1000.0) as i64);
+        time.format(format).to_string()
+    })
+}
+
+fn render_key(key: Option<&str>) -> String {
+    match key {
+        Some(k) => format!("[{}] ", k),
+        None => String::new(),
+    }
+}
+
+fn render_diff_list_item(line: &AnnotatedLine, key: Option<&str>, offset: usize) -> String {
     let contents = if offset >= line.line.len() {
         ""
     } else {
         &line.line[offset..]
     };
     format!(
-        "{:<10} {:10} {}",
+        "{:<10} {:10} {}{}",
         line.line_number,
         line.elapsed_string(),
+        render_key(key),
         contents
     )
 }
 
+fn diff_list_title(app: &App) -> String {
+    let selection = match app.diff_list.selection {
+        DiffSelection::Largest => "Largest",
+        DiffSelection::SmallestNonZero => "Smallest non-zero",
+    };
+    let order = match app.diff_list.order {
+        DiffOrder::Elapsed => "elapsed time",
+        DiffOrder::LineNumber => "line number",
+        DiffOrder::Timestamp => "timestamp",
+    };
+    let key = match app.key_name(app.key_filter) {
+        Some(key) => format!(" for key {}", key),
+        None => String::new(),
+    };
+    format!(
+        "{} {} diffs{}, by {}",
+        selection,
+        app.largest_diffs.len(),
+        key,
+        order
+    )
+}
+
 fn draw_diff_list<B: Backend>(frame: &mut Frame<B>, app: &mut App, rect: Rect) {
+    let theme = app.theme;
     if app.help_mode {
         let text = vec![
-            Title(CYAN, "Navigation"),
+            Title(theme.primary, "Navigation"),
+            Gap(1),
+            Title(theme.emphasis, "Vertical scrolling"),
+            Body(
+                "{up}/{down}, {page_up}/{page_down}, {top}/{bottom} for the first and last entries",
+            ),
             Gap(1),
-            Title(WHITE, "Vertical scrolling"),
-            Body("Up/Down, PageUp/PageDown"),
+            Title(theme.emphasis, "Horizontal scrolling"),
+            Body("{left}/{right}, {home}/{end}"),
             Gap(1),
-            Title(WHITE, "Horizontal scrolling"),
-            Body("Left/Right, Home/End"),
+            Title(theme.emphasis, "Jump-to-line"),
+            Body("{select}"),
             Gap(1),
-            Title(WHITE, "Jump-to-line"),
-            Body("Enter"),
+            Title(theme.emphasis, "Filter by key"),
+            Body(
+                "{cycle_key_filter} cycles through the keys found using --key-regex or --key-field",
+            ),
+            Gap(1),
+            Title(theme.emphasis, "Size and order"),
+            Body(
+                "{grow} doubles the number of lines shown, {shrink} halves it
+{cycle_order} cycles between ordering by elapsed time, line number and timestamp
+{toggle_smallest} toggles between the largest and smallest non-zero elapsed times",
+            ),
         ];
-        let text = help_text(&text);
+        let text = help_text(app, &text);
 
         Paragraph::new(text.iter())
             .alignment(Alignment::Center)
             .block(
                 Block::default()
-                    .status(app.status(Panel::List))
+                    .status(&theme, app.status(Panel::List))
                     .borders(Borders::ALL)
                     .title("Largest diffs panel"),
             )
-            .style(default_style())
+            .style(theme.base())
             .render(frame, rect);
 
         return;
@@ -404,7 +1043,9 @@ fn draw_diff_list<B: Backend>(frame: &mut Frame<B>, app: &mut App, rect: Rect) {
     let deltas: Vec<_> = app
         .largest_diffs
         .iter()
-        .map(|line| render_diff_list_item(line, app.horizontal_diff_scroll()))
+        .map(|line| {
+            render_diff_list_item(line, app.key_name(line.key), app.horizontal_diff_scroll())
+        })
         .collect();
 
     let deltas: Vec<_> = deltas.iter().map(|x| x as &str).collect();
@@ -412,14 +1053,169 @@ fn draw_diff_list<B: Backend>(frame: &mut Frame<B>, app: &mut App, rect: Rect) {
     SelectableList::default()
         .block(
             Block::default()
-                .title("Largest diffs")
-                .style(default_style())
-                .status(app.status(Panel::List)),
+                .title(&diff_list_title(app))
+                .style(theme.base())
+                .status(&theme, app.status(Panel::List)),
         )
         .items(&deltas)
         .select(Some(app.vertical_diff_scroll()))
-        .style(default_style().fg(FOREGROUND))
-        .highlight_style(Style::default().bg(FOREGROUND).fg(BACKGROUND))
+        .style(theme.base())
+        .highlight_style(theme.fill(theme.foreground))
+        .highlight_symbol(">>")
+        .render(frame, rect)
+}
+
+fn render_span_list_item(span: &Span, line: &AnnotatedLine, offset: usize) -> String {
+    // Skipping characters rather than bytes, as lines can contain multibyte characters
+    let contents: String = line.line.chars().skip(offset).collect();
+    format!(
+        "{:<10} {:<10} {:10} {}{}",
+        span.start,
+        span.end,
+        render_duration(span.duration),
+        render_key(span.key.as_deref()),
+        contents
+    )
+}
+
+fn draw_span_list<B: Backend>(frame: &mut Frame<B>, app: &mut App, rect: Rect) {
+    let theme = app.theme;
+    if app.help_mode {
+        let text = vec![
+            Body(
+                "This panel shows spans between lines matching the start and end patterns
+provided on the command line, ordered by decreasing duration",
+            ),
+            Gap(1),
+            Title(theme.primary, "Navigation"),
+            Gap(1),
+            Title(theme.emphasis, "Vertical scrolling"),
+            Body(
+                "{up}/{down}, {page_up}/{page_down}, {top}/{bottom} for the first and last entries",
+            ),
+            Gap(1),
+            Title(theme.emphasis, "Horizontal scrolling"),
+            Body("{left}/{right}, {home}/{end}"),
+            Gap(1),
+            Title(theme.emphasis, "Jump-to-start-line"),
+            Body("{select}"),
+        ];
+        let text = help_text(app, &text);
+
+        Paragraph::new(text.iter())
+            .alignment(Alignment::Center)
+            .block(
+                Block::default()
+                    .status(&theme, app.status(Panel::Spans))
+                    .borders(Borders::ALL)
+                    .title("Spans panel"),
+            )
+            .style(theme.base())
+            .render(frame, rect);
+
+        return;
+    }
+
+    let spans: Vec<_> = app
+        .spans
+        .iter()
+        .map(|span| {
+            render_span_list_item(span, &app.lines[span.start], app.horizontal_span_scroll())
+        })
+        .collect();
+
+    let spans: Vec<_> = spans.iter().map(|x| x as &str).collect();
+
+    SelectableList::default()
+        .block(
+            Block::default()
+                .title(&format!("Spans ({})", app.spans.len()))
+                .style(theme.base())
+                .status(&theme, app.status(Panel::Spans)),
+        )
+        .items(&spans)
+        .select(Some(app.vertical_span_scroll()))
+        .style(theme.base())
+        .highlight_style(theme.fill(theme.foreground))
+        .highlight_symbol(">>")
+        .render(frame, rect)
+}
+
+fn render_template_list_item(stats: &TemplateStats, template: &str, offset: usize) -> String {
+    // Skipping characters rather than bytes, as templates can contain multibyte characters
+    let contents: String = template.chars().skip(offset).collect();
+    format!(
+        "{:10} {:<8} {:10} {:10} {:10} {:10} {}",
+        render_duration(stats.total),
+        stats.count,
+        render_duration(stats.mean),
+        render_duration(stats.p50),
+        render_duration(stats.p99),
+        render_duration(stats.max),
+        contents
+    )
+}
+
+fn draw_template_list<B: Backend>(frame: &mut Frame<B>, app: &mut App, rect: Rect) {
+    let theme = app.theme;
+    if app.help_mode {
+        let text = vec![
+            Body("Columns are total, count, mean, 50th percentile, 99th percentile and maximum"),
+            Gap(1),
+            Title(theme.primary, "Navigation"),
+            Gap(1),
+            Title(theme.emphasis, "Vertical scrolling"),
+            Body(
+                "{up}/{down}, {page_up}/{page_down}, {top}/{bottom} for the first and last entries",
+            ),
+            Gap(1),
+            Title(theme.emphasis, "Horizontal scrolling"),
+            Body("{left}/{right}, {home}/{end}"),
+            Gap(1),
+            Title(theme.emphasis, "Jump-to-slowest-line"),
+            Body("{select}"),
+        ];
+        let text = help_text(app, &text);
+
+        Paragraph::new(text.iter())
+            .alignment(Alignment::Center)
+            .block(
+                Block::default()
+                    .status(&theme, app.status(Panel::Templates))
+                    .borders(Borders::ALL)
+                    .title("Templates panel"),
+            )
+            .style(theme.base())
+            .render(frame, rect);
+
+        return;
+    }
+
+    let templates: Vec<_> = app
+        .template_stats
+        .iter()
+        .map(|stats| {
+            render_template_list_item(
+                stats,
+                &app.templates.names[stats.id],
+                app.horizontal_template_scroll(),
+            )
+        })
+        .collect();
+
+    let templates: Vec<_> = templates.iter().map(|x| x as &str).collect();
+
+    SelectableList::default()
+        .block(
+            Block::default()
+                .title("Templates (total | count | mean | p50 | p99 | max)")
+                .style(theme.base())
+                .status(&theme, app.status(Panel::Templates)),
+        )
+        .items(&templates)
+        .select(Some(app.vertical_template_scroll()))
+        .style(theme.base())
+        .highlight_style(theme.fill(theme.foreground))
         .highlight_symbol(">>")
         .render(frame, rect)
 }
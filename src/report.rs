@@ -0,0 +1,82 @@
+//! Writes a plain text summary of a log's elapsed times, for use outside the TUI
+
+use crate::app::{render_seconds, AnnotatedLine, App};
+use std::io::{self, Write};
+
+/// The `top` lines with the largest elapsed times, in decreasing order of elapsed time.
+pub fn largest_elapsed<'a>(app: &'a App, top: usize) -> Vec<&'a AnnotatedLine<'a>> {
+    let mut largest: Vec<_> = app.lines.iter().collect();
+    largest.sort_by(|x, y| {
+        y.elapsed
+            .cmp(&x.elapsed)
+            .then(x.line_number.cmp(&y.line_number))
+    });
+    largest.truncate(top);
+    largest
+}
+
+/// Writes the line count, total duration, elapsed time percentiles and the `top`
+/// largest elapsed times with their line numbers and text.
+pub fn write_report<W: Write>(app: &App, top: usize, out: &mut W) -> io::Result<()> {
+    let first = &app.lines[0];
+    let last = &app.lines[app.lines.len() - 1];
+    let percentiles = app.elapsed_percentiles;
+
+    writeln!(out, "Lines: {}", app.lines.len())?;
+    writeln!(
+        out,
+        "Duration: {}",
+        render_seconds(last.timestamp - first.timestamp)
+    )?;
+    writeln!(
+        out,
+        "Elapsed time percentiles: p50 {}, p90 {}, p99 {}, max {}",
+        render_seconds(percentiles.p50),
+        render_seconds(percentiles.p90),
+        render_seconds(percentiles.p99),
+        render_seconds(percentiles.max)
+    )?;
+
+    writeln!(out)?;
+    writeln!(out, "Largest elapsed times:")?;
+    let line_number_width = (app.lines.len() - 1).to_string().len();
+    for line in largest_elapsed(app, top) {
+        writeln!(
+            out,
+            "{:>width$}  {:>12}  {}",
+            line.line_number,
+            render_seconds(line.elapsed),
+            line.line,
+            width = line_number_width
+        )?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::tests::log_lines;
+    use crate::app::Settings;
+
+    #[test]
+    fn test_write_report() {
+        let lines = log_lines(&[(0, "start"), (1000, "a"), (1500, "b"), (5500, "c")]);
+        let app = App::new(&lines, &Settings::default());
+
+        let mut out = Vec::new();
+        write_report(&app, 2, &mut out).unwrap();
+
+        let expected = "\
+Lines: 4
+Duration: 5.500s
+Elapsed time percentiles: p50 0.500s, p90 4.000s, p99 4.000s, max 4.000s
+
+Largest elapsed times:
+3        4.000s  2020-02-29 10:00:05.500Z c
+1        1.000s  2020-02-29 10:00:01.000Z a
+";
+        assert_eq!(String::from_utf8(out).unwrap(), expected);
+    }
+}
@@ -0,0 +1,133 @@
+//! Pairs lines matching user-provided start and end patterns into timed spans
+
+use chrono::{DateTime, Duration, Utc};
+use regex::Regex;
+use std::collections::HashMap;
+
+/// Name of the capture group used to correlate start and end lines.
+pub const KEY_GROUP: &str = "key";
+
+/// A span is opened by a line matching `start` and closed by the next line matching `end`.
+/// If the patterns contain a capture group named `key` then a span is only closed by an
+/// end line capturing the same value, which allows interleaved spans to be paired correctly.
+#[derive(Debug, Clone)]
+pub struct SpanRule {
+    pub start: Regex,
+    pub end: Regex,
+}
+
+impl SpanRule {
+    pub fn new(start: &str, end: &str) -> Result<SpanRule, regex::Error> {
+        Ok(SpanRule {
+            start: Regex::new(start)?,
+            end: Regex::new(end)?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Span {
+    /// Index of the rule which produced this span
+    pub rule: usize,
+    /// The value of the `key` capture group, if any
+    pub key: Option<String>,
+    /// Line number of the line which opened this span
+    pub start: usize,
+    /// Line number of the line which closed this span
+    pub end: usize,
+    pub duration: Duration,
+}
+
+/// Returns `None` if `regex` does not match `line`, and otherwise the captured key, if any.
+fn match_key(regex: &Regex, line: &str) -> Option<Option<String>> {
+    let captures = regex.captures(line)?;
+    Some(captures.name(KEY_GROUP).map(|m| m.as_str().to_string()))
+}
+
+/// Finds all spans matching any of `rules`, sorted by decreasing duration.
+/// Spans which are never closed are ignored. When several spans with the same key are
+/// open at once an end line closes the most recently opened one.
+pub fn find_spans(rules: &[SpanRule], lines: &[&str], timestamps: &[DateTime<Utc>]) -> Vec<Span> {
+    assert_eq!(lines.len(), timestamps.len());
+
+    let mut spans = Vec::new();
+
+    for (rule_index, rule) in rules.iter().enumerate() {
+        let mut open: HashMap<Option<String>, Vec<usize>> = HashMap::new();
+
+        for (i, line) in lines.iter().enumerate() {
+            // Check for the end of a span first, so that a line matching both
+            // patterns closes the previous span before opening the next one
+            if let Some(key) = match_key(&rule.end, line) {
+                if let Some(start) = open.get_mut(&key).and_then(|s| s.pop()) {
+                    spans.push(Span {
+                        rule: rule_index,
+                        key,
+                        start,
+                        end: i,
+                        duration: timestamps[i] - timestamps[start],
+                    });
+                }
+            }
+            if let Some(key) = match_key(&rule.start, line) {
+                open.entry(key).or_default().push(i);
+            }
+        }
+    }
+
+    spans.sort_by(|x, y| y.duration.cmp(&x.duration).then(x.start.cmp(&y.start)));
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::tests::timestamps;
+
+    fn summary(spans: &[Span]) -> Vec<(usize, usize, i64)> {
+        spans
+            .iter()
+            .map(|s| (s.start, s.end, s.duration.num_seconds()))
+            .collect()
+    }
+
+    #[test]
+    fn find_spans_without_key() {
+        let lines = vec!["begin", "work", "end", "begin", "end", "end"];
+        let times = timestamps(&[0, 1, 2, 10, 15, 20]);
+        let rules = vec![SpanRule::new("begin", "end").unwrap()];
+        let spans = find_spans(&rules, &lines, &times);
+        assert_eq!(summary(&spans), vec![(3, 4, 5), (0, 2, 2)]);
+        assert!(spans.iter().all(|s| s.key.is_none()));
+    }
+
+    #[test]
+    fn find_spans_with_key() {
+        let lines = vec![
+            "start request=a",
+            "start request=b",
+            "finish request=a",
+            "finish request=c",
+            "finish request=b",
+        ];
+        let times = timestamps(&[0, 1, 3, 4, 9]);
+        let rules = vec![SpanRule::new(
+            r"start request=(?P<key>\w+)",
+            r"finish request=(?P<key>\w+)",
+        )
+        .unwrap()];
+        let spans = find_spans(&rules, &lines, &times);
+        assert_eq!(summary(&spans), vec![(1, 4, 8), (0, 2, 3)]);
+        assert_eq!(spans[0].key, Some("b".into()));
+        assert_eq!(spans[1].key, Some("a".into()));
+    }
+
+    #[test]
+    fn find_spans_line_matching_start_and_end() {
+        let lines = vec!["step", "step", "other", "step"];
+        let times = timestamps(&[0, 2, 3, 7]);
+        let rules = vec![SpanRule::new("step", "step").unwrap()];
+        let spans = find_spans(&rules, &lines, &times);
+        assert_eq!(summary(&spans), vec![(1, 3, 5), (0, 1, 2)]);
+    }
+}
@@ -0,0 +1,99 @@
+//! Summary statistics for elapsed times
+
+use chrono::Duration;
+
+/// Returns the `p`th percentile of `sorted` using the nearest-rank method.
+/// `sorted` must be non-empty and in increasing order, and `p` must be in [0, 100].
+pub fn percentile<T: Copy>(sorted: &[T], p: f64) -> T {
+    assert!(!sorted.is_empty());
+    let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.max(1).min(sorted.len()) - 1]
+}
+
+/// Percentiles of a set of elapsed times.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Percentiles {
+    pub p50: Duration,
+    pub p90: Duration,
+    pub p99: Duration,
+    pub max: Duration,
+}
+
+impl Percentiles {
+    /// `sorted` must be in increasing order.
+    pub fn new(sorted: &[Duration]) -> Percentiles {
+        if sorted.is_empty() {
+            return Percentiles {
+                p50: Duration::zero(),
+                p90: Duration::zero(),
+                p99: Duration::zero(),
+                max: Duration::zero(),
+            };
+        }
+        Percentiles {
+            p50: percentile(sorted, 50.0),
+            p90: percentile(sorted, 90.0),
+            p99: percentile(sorted, 99.0),
+            max: sorted[sorted.len() - 1],
+        }
+    }
+}
+
+/// Parses a duration such as "5s", "1.5s", "250ms", "2m" or "1h". A number without a
+/// unit is taken to be in seconds.
+pub fn parse_duration(s: &str) -> Result<Duration, failure::Error> {
+    let s = s.trim();
+    let split = s
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+    let number: f64 = number
+        .parse()
+        .map_err(|_| failure::format_err!("Invalid duration '{}'", s))?;
+    let millis_per_unit = match unit.trim() {
+        "ms" => 1.0,
+        "" | "s" => 1000.0,
+        "m" => 60_000.0,
+        "h" => 3_600_000.0,
+        _ => {
+            return Err(failure::format_err!(
+                "Invalid duration unit in '{}', expected 'ms', 's', 'm' or 'h'",
+                s
+            ))
+        }
+    };
+    Ok(Duration::microseconds(
+        (number * millis_per_unit * 1000.0).round() as i64,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentile() {
+        let values = vec![15, 20, 35, 40, 50];
+        assert_eq!(percentile(&values, 0.0), 15);
+        assert_eq!(percentile(&values, 5.0), 15);
+        assert_eq!(percentile(&values, 30.0), 20);
+        assert_eq!(percentile(&values, 40.0), 20);
+        assert_eq!(percentile(&values, 50.0), 35);
+        assert_eq!(percentile(&values, 100.0), 50);
+        assert_eq!(percentile(&[7], 99.0), 7);
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("5s").unwrap(), Duration::seconds(5));
+        assert_eq!(parse_duration("1.5").unwrap(), Duration::milliseconds(1500));
+        assert_eq!(
+            parse_duration("250ms").unwrap(),
+            Duration::milliseconds(250)
+        );
+        assert_eq!(parse_duration("2m").unwrap(), Duration::minutes(2));
+        assert_eq!(parse_duration("1h").unwrap(), Duration::hours(1));
+        assert!(parse_duration("5 days").is_err());
+        assert!(parse_duration("s").is_err());
+    }
+}
@@ -0,0 +1,330 @@
+//! Minimal SVG rendering of charts, for reports viewed outside the TUI
+
+use std::fmt::Write;
+
+/// Escapes text for inclusion in HTML or SVG content and attribute values.
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// Maps `value` from `bounds` to [0, `length`], treating empty bounds as covering a single point
+fn scale(value: f64, bounds: (f64, f64), length: f64) -> f64 {
+    let extent = bounds.1 - bounds.0;
+    if extent > 0.0 {
+        (value - bounds.0) / extent * length
+    } else {
+        0.0
+    }
+}
+
+// The area of an image in which data is plotted, and the data bounds mapped to its edges
+struct Plot {
+    left: f64,
+    top: f64,
+    width: f64,
+    height: f64,
+    x_bounds: (f64, f64),
+    y_bounds: (f64, f64),
+}
+
+impl Plot {
+    // Image coordinates of a data point, with y increasing upwards
+    fn position(&self, (x, y): (f64, f64)) -> (f64, f64) {
+        (
+            self.left + scale(x, self.x_bounds, self.width),
+            self.top + self.height - scale(y, self.y_bounds, self.height),
+        )
+    }
+
+    fn polyline_points(&self, points: &[(f64, f64)]) -> String {
+        let mut coordinates = String::new();
+        for point in points {
+            let (x, y) = self.position(*point);
+            write!(coordinates, "{:.1},{:.1} ", x, y).unwrap();
+        }
+        coordinates.trim_end().to_string()
+    }
+}
+
+/// Renders `points` as a line filling a `width` by `height` image, with `x_bounds` and
+/// `y_bounds` mapped to the edges of the image and y increasing upwards.
+pub fn line_chart(
+    points: &[(f64, f64)],
+    x_bounds: (f64, f64),
+    y_bounds: (f64, f64),
+    width: usize,
+    height: usize,
+) -> String {
+    let plot = Plot {
+        left: 0.0,
+        top: 0.0,
+        width: width as f64,
+        height: height as f64,
+        x_bounds,
+        y_bounds,
+    };
+    format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}"><polyline class="line" fill="none" points="{points}"/></svg>"#,
+        w = width,
+        h = height,
+        points = plot.polyline_points(points)
+    )
+}
+
+// Colours matching the TUI's default theme
+const BACKGROUND: &str = "#282a36";
+const FOREGROUND: &str = "#f8f8f2";
+pub const CYAN: &str = "#8be9fd";
+pub const GREEN: &str = "#50fa7b";
+const ORANGE: &str = "#ffb86c";
+const RED: &str = "#ff5555";
+
+// Space around the plot for axis labels and titles
+const MARGIN_LEFT: f64 = 80.0;
+const MARGIN_RIGHT: f64 = 40.0;
+const MARGIN_TOP: f64 = 20.0;
+const MARGIN_BOTTOM: f64 = 50.0;
+
+/// A named line on a chart.
+pub struct Series<'a> {
+    pub name: &'a str,
+    pub points: &'a [(f64, f64)],
+    pub colour: &'a str,
+}
+
+/// A line chart with labelled axes, an optional current position and marked points.
+/// Charts with more than one series include a legend.
+pub struct Chart<'a> {
+    pub series: &'a [Series<'a>],
+    pub x_bounds: (f64, f64),
+    pub y_bounds: (f64, f64),
+    /// Spread evenly along each axis, from the lower bound to the upper bound
+    pub x_labels: &'a [String],
+    pub y_labels: &'a [String],
+    pub x_title: &'a str,
+    pub y_title: &'a str,
+    /// Drawn as a vertical line through the point
+    pub current: Option<(f64, f64)>,
+    pub markers: &'a [(f64, f64)],
+}
+
+// Positions of `count` labels spread evenly from `start` to `start + length`
+fn label_positions(count: usize, start: f64, length: f64) -> impl Iterator<Item = f64> {
+    let step = length / (count.max(2) - 1) as f64;
+    (0..count).map(move |i| start + i as f64 * step)
+}
+
+/// Renders `chart` as a standalone `width` by `height` SVG image.
+pub fn chart(chart: &Chart, width: usize, height: usize) -> String {
+    let plot = Plot {
+        left: MARGIN_LEFT,
+        top: MARGIN_TOP,
+        width: (width as f64 - MARGIN_LEFT - MARGIN_RIGHT).max(1.0),
+        height: (height as f64 - MARGIN_TOP - MARGIN_BOTTOM).max(1.0),
+        x_bounds: chart.x_bounds,
+        y_bounds: chart.y_bounds,
+    };
+    let bottom = plot.top + plot.height;
+    let right = plot.left + plot.width;
+
+    let mut svg = String::new();
+    write!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}" font-family="sans-serif" font-size="12">
+<rect width="100%" height="100%" fill="{background}"/>
+<path d="M{left},{top} V{bottom} H{right}" fill="none" stroke="{foreground}"/>
+"#,
+        w = width,
+        h = height,
+        background = BACKGROUND,
+        foreground = FOREGROUND,
+        left = plot.left,
+        top = plot.top,
+        bottom = bottom,
+        right = right
+    )
+    .unwrap();
+
+    let positions = label_positions(chart.x_labels.len(), plot.left, plot.width);
+    for (label, x) in chart.x_labels.iter().zip(positions) {
+        writeln!(
+            svg,
+            r#"<text x="{:.1}" y="{:.1}" fill="{}" text-anchor="middle">{}</text>"#,
+            x,
+            bottom + 16.0,
+            FOREGROUND,
+            escape(label)
+        )
+        .unwrap();
+    }
+    let positions = label_positions(chart.y_labels.len(), bottom, -plot.height);
+    for (label, y) in chart.y_labels.iter().zip(positions) {
+        writeln!(
+            svg,
+            r#"<text x="{:.1}" y="{:.1}" fill="{}" text-anchor="end" dominant-baseline="middle">{}</text>"#,
+            plot.left - 6.0,
+            y,
+            FOREGROUND,
+            escape(label)
+        )
+        .unwrap();
+    }
+    writeln!(
+        svg,
+        r#"<text x="{:.1}" y="{:.1}" fill="{}" text-anchor="middle">{}</text>"#,
+        plot.left + plot.width / 2.0,
+        bottom + 40.0,
+        FOREGROUND,
+        escape(chart.x_title)
+    )
+    .unwrap();
+    writeln!(
+        svg,
+        r#"<text transform="translate(16,{:.1}) rotate(-90)" fill="{}" text-anchor="middle">{}</text>"#,
+        plot.top + plot.height / 2.0,
+        FOREGROUND,
+        escape(chart.y_title)
+    )
+    .unwrap();
+
+    for (i, series) in chart.series.iter().enumerate() {
+        writeln!(
+            svg,
+            r#"<polyline fill="none" stroke="{}" stroke-width="1.5" points="{}"/>"#,
+            series.colour,
+            plot.polyline_points(series.points)
+        )
+        .unwrap();
+        if chart.series.len() > 1 {
+            writeln!(
+                svg,
+                r#"<text x="{:.1}" y="{:.1}" fill="{}">{}</text>"#,
+                plot.left + 10.0,
+                plot.top + 16.0 * (i + 1) as f64,
+                series.colour,
+                escape(series.name)
+            )
+            .unwrap();
+        }
+    }
+    for marker in chart.markers {
+        let (x, y) = plot.position(*marker);
+        writeln!(
+            svg,
+            r#"<circle cx="{:.1}" cy="{:.1}" r="3" fill="{}"/>"#,
+            x, y, ORANGE
+        )
+        .unwrap();
+    }
+    if let Some(current) = chart.current {
+        let (x, _) = plot.position(current);
+        writeln!(
+            svg,
+            r#"<line x1="{x:.1}" y1="{top}" x2="{x:.1}" y2="{bottom}" stroke="{colour}"/>"#,
+            x = x,
+            top = plot.top,
+            bottom = bottom,
+            colour = RED
+        )
+        .unwrap();
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Renders one bar per count, scaled so that the largest count fills the height of the image.
+pub fn bar_chart(counts: &[u64], width: usize, height: usize) -> String {
+    let max = counts.iter().cloned().max().unwrap_or(0).max(1) as f64;
+    let bar_width = width as f64 / counts.len().max(1) as f64;
+    let mut bars = String::new();
+    for (i, count) in counts.iter().enumerate() {
+        let bar_height = *count as f64 / max * height as f64;
+        write!(
+            bars,
+            r#"<rect class="bar" x="{:.1}" y="{:.1}" width="{:.1}" height="{:.1}"><title>{}</title></rect>"#,
+            i as f64 * bar_width,
+            height as f64 - bar_height,
+            bar_width,
+            bar_height,
+            count
+        )
+        .unwrap();
+    }
+    format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">{bars}</svg>"#,
+        w = width,
+        h = height,
+        bars = bars
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(
+            escape(r#"<a href="x">&'"#),
+            "&lt;a href=&quot;x&quot;&gt;&amp;&#39;"
+        );
+    }
+
+    #[test]
+    fn test_line_chart() {
+        let svg = line_chart(
+            &[(0.0, 0.0), (5.0, 0.5), (10.0, 1.0)],
+            (0.0, 10.0),
+            (0.0, 1.0),
+            100,
+            50,
+        );
+        assert!(svg.contains(r#"points="0.0,50.0 50.0,25.0 100.0,0.0""#));
+    }
+
+    #[test]
+    fn test_chart() {
+        let labels = vec!["0".to_string(), "5".to_string(), "10".to_string()];
+        let svg = chart(
+            &Chart {
+                series: &[Series {
+                    name: "Line",
+                    points: &[(0.0, 0.0), (10.0, 1.0)],
+                    colour: CYAN,
+                }],
+                x_bounds: (0.0, 10.0),
+                y_bounds: (0.0, 1.0),
+                x_labels: &labels,
+                y_labels: &[],
+                x_title: "Line number",
+                y_title: "Fraction <of> time",
+                current: Some((5.0, 0.5)),
+                markers: &[(10.0, 1.0)],
+            },
+            220,
+            170,
+        );
+        // The plot is 100 pixels square, offset by the margins
+        assert!(svg.contains(r#"points="80.0,120.0 180.0,20.0""#));
+        assert!(svg.contains(
+            r##"<text x="130.0" y="136.0" fill="#f8f8f2" text-anchor="middle">5</text>"##
+        ));
+        assert!(svg.contains(r##"<circle cx="180.0" cy="20.0" r="3" fill="#ffb86c"/>"##));
+        assert!(
+            svg.contains(r##"<line x1="130.0" y1="20" x2="130.0" y2="120" stroke="#ff5555"/>"##)
+        );
+        assert!(svg.contains("Fraction &lt;of&gt; time"));
+    }
+}
@@ -0,0 +1,235 @@
+//! Groups log lines into templates by masking variable tokens such as numbers, IDs
+//! and hex strings, and aggregates the elapsed times attributed to each template
+
+use crate::stats::percentile;
+use chrono::Duration;
+use rayon::prelude::*;
+use std::collections::HashMap;
+
+/// Replaces the variable parts of a line in its template.
+pub const WILDCARD: &str = "<*>";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Piece<'a> {
+    Literal(&'a str),
+    Variable,
+}
+
+// Numbers, IDs and hashes almost always contain a digit. Hex strings without
+// digits are only treated as variable if they are long enough to be unlikely words.
+fn is_variable(word: &str) -> bool {
+    word.chars().any(|c| c.is_ascii_digit())
+        || (word.len() >= 8 && word.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// Splits a whitespace-free token into alternating runs of alphanumeric and other characters.
+fn pieces(token: &str) -> Vec<Piece<'_>> {
+    let mut pieces = Vec::new();
+    let mut start = 0;
+    let mut chars = token.char_indices().peekable();
+
+    while let Some((_, c)) = chars.next() {
+        let is_word = c.is_alphanumeric();
+        let end = match chars.peek() {
+            Some((_, next)) if next.is_alphanumeric() == is_word => continue,
+            Some((j, _)) => *j,
+            None => token.len(),
+        };
+        let run = &token[start..end];
+        if is_word && is_variable(run) {
+            pieces.push(Piece::Variable);
+        } else {
+            pieces.push(Piece::Literal(run));
+        }
+        start = end;
+    }
+
+    pieces
+}
+
+fn mask_token(token: &str, output: &mut String) {
+    let pieces = pieces(token);
+    let mut i = 0;
+    while i < pieces.len() {
+        match pieces[i] {
+            Piece::Literal(l) => {
+                output.push_str(l);
+                i += 1;
+            }
+            Piece::Variable => {
+                output.push_str(WILDCARD);
+                i += 1;
+                // Merge variables separated only by punctuation, e.g. "1.5" or "10.0.0.1"
+                while i + 1 < pieces.len() && pieces[i + 1] == Piece::Variable {
+                    i += 2;
+                }
+            }
+        }
+    }
+}
+
+/// Returns the template for a log line, i.e. the line with all variable parts
+/// replaced by `WILDCARD` and runs of whitespace replaced by a single space.
+pub fn template(line: &str) -> String {
+    let mut output = String::with_capacity(line.len());
+    for (i, token) in line.split_whitespace().enumerate() {
+        if i > 0 {
+            output.push(' ');
+        }
+        mask_token(token, &mut output);
+    }
+    output
+}
+
+/// The distinct templates in a log, and the template of each line.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Templates {
+    pub names: Vec<String>,
+    /// Index into `names` for each log line
+    pub ids: Vec<usize>,
+}
+
+impl Templates {
+    #[inline(never)]
+    pub fn new(lines: &[&str]) -> Templates {
+        let templates: Vec<_> = lines.par_iter().map(|l| template(l)).collect();
+
+        let mut names = Vec::new();
+        let mut lookup = HashMap::new();
+        let mut ids = Vec::with_capacity(templates.len());
+
+        for t in templates {
+            let next_id = names.len();
+            let id = *lookup.entry(t.clone()).or_insert(next_id);
+            if id == next_id {
+                names.push(t);
+            }
+            ids.push(id);
+        }
+
+        Templates { names, ids }
+    }
+
+    /// Aggregates `elapsed`, which contains the elapsed time attributed to each
+    /// log line, by template. The result is sorted by decreasing total elapsed time.
+    #[inline(never)]
+    pub fn stats(&self, elapsed: &[Duration]) -> Vec<TemplateStats> {
+        assert_eq!(elapsed.len(), self.ids.len());
+
+        let mut groups: Vec<Vec<(Duration, usize)>> = vec![Vec::new(); self.names.len()];
+        for (line_number, (id, e)) in self.ids.iter().zip(elapsed).enumerate() {
+            groups[*id].push((*e, line_number));
+        }
+
+        let mut stats: Vec<_> = groups
+            .into_par_iter()
+            .enumerate()
+            .filter(|(_, g)| !g.is_empty())
+            .map(|(id, group)| TemplateStats::new(id, group))
+            .collect();
+
+        stats.sort_by(|x, y| y.total.cmp(&x.total).then(x.id.cmp(&y.id)));
+        stats
+    }
+}
+
+/// Summary of the elapsed times attributed to the lines matching a template.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateStats {
+    /// Index into `Templates::names`
+    pub id: usize,
+    pub count: usize,
+    pub total: Duration,
+    pub mean: Duration,
+    pub p50: Duration,
+    pub p99: Duration,
+    pub max: Duration,
+    /// The line with the largest elapsed time
+    pub max_line: usize,
+}
+
+impl TemplateStats {
+    // `group` contains (elapsed time, line number) pairs
+    fn new(id: usize, mut group: Vec<(Duration, usize)>) -> TemplateStats {
+        group.sort();
+        let elapsed: Vec<_> = group.iter().map(|g| g.0).collect();
+        let count = elapsed.len();
+        let total = elapsed.iter().fold(Duration::zero(), |acc, e| acc + *e);
+        let (max, max_line) = group[count - 1];
+
+        TemplateStats {
+            id,
+            count,
+            total,
+            mean: total / count as i32,
+            p50: percentile(&elapsed, 50.0),
+            p99: percentile(&elapsed, 99.0),
+            max,
+            max_line,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_template() {
+        let cases = vec![
+            (
+                "2020-02-29 10:11:12.345Z  Processed request 17 in 1.5s",
+                "<*> <*> Processed request <*> in <*>",
+            ),
+            ("Connected to 10.0.0.1:8080", "Connected to <*>"),
+            (
+                "[worker-3] commit deadbeefcafe id=a1b2 done",
+                "[worker-<*>] commit <*> id=<*> done",
+            ),
+            ("Starting up", "Starting up"),
+        ];
+        for (line, expected) in cases {
+            assert_eq!(template(line), expected, "{}", line);
+        }
+    }
+
+    #[test]
+    fn test_template_stats() {
+        let lines = vec!["a 1", "b", "a 2", "a 3", "b"];
+        let templates = Templates::new(&lines);
+        assert_eq!(templates.names, vec!["a <*>", "b"]);
+        assert_eq!(templates.ids, vec![0, 1, 0, 0, 1]);
+
+        let elapsed: Vec<_> = vec![0, 10, 2, 4, 5]
+            .into_iter()
+            .map(Duration::seconds)
+            .collect();
+        let stats = templates.stats(&elapsed);
+
+        assert_eq!(
+            stats,
+            vec![
+                TemplateStats {
+                    id: 1,
+                    count: 2,
+                    total: Duration::seconds(15),
+                    mean: Duration::milliseconds(7500),
+                    p50: Duration::seconds(5),
+                    p99: Duration::seconds(10),
+                    max: Duration::seconds(10),
+                    max_line: 1,
+                },
+                TemplateStats {
+                    id: 0,
+                    count: 3,
+                    total: Duration::seconds(6),
+                    mean: Duration::seconds(2),
+                    p50: Duration::seconds(2),
+                    p99: Duration::seconds(4),
+                    max: Duration::seconds(4),
+                    max_line: 3,
+                },
+            ]
+        );
+    }
+}
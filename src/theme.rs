@@ -0,0 +1,173 @@
+//! Colour themes for the TUI
+
+use crate::app::Status;
+use serde::{Deserialize, Serialize};
+use tui::style::{Color, Modifier, Style};
+
+/// The built-in themes, selected by the "theme" field of the config file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ThemeName {
+    #[default]
+    #[serde(rename = "dark")]
+    Dark,
+    #[serde(rename = "light")]
+    Light,
+    /// The dark theme restricted to the 256 colour palette, for terminals without 24-bit colour
+    #[serde(rename = "256")]
+    Dark256,
+    /// Uses the terminal's default colours, with bold and reversed text for emphasis
+    #[serde(rename = "monochrome")]
+    Monochrome,
+}
+
+/// The colours used by the TUI, by role.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Theme {
+    pub foreground: Color,
+    pub background: Color,
+    /// The active panel, the current line and anything needing attention
+    pub accent: Color,
+    /// Elapsed time bars, help titles and markers
+    pub highlight: Color,
+    /// Plotted data
+    pub primary: Color,
+    /// Selections and secondary markers
+    pub emphasis: Color,
+    /// If true then colours are ignored, and text in any colour other than the foreground
+    /// colour is bold
+    pub monochrome: bool,
+}
+
+impl Theme {
+    pub fn new(name: ThemeName) -> Theme {
+        match name {
+            ThemeName::Dark => Theme {
+                foreground: Color::Rgb(248, 248, 242),
+                background: Color::Rgb(40, 42, 54),
+                accent: Color::Rgb(255, 85, 85),
+                highlight: Color::Rgb(255, 184, 108),
+                primary: Color::Rgb(139, 233, 253),
+                emphasis: Color::Rgb(255, 255, 255),
+                monochrome: false,
+            },
+            ThemeName::Light => Theme {
+                foreground: Color::Rgb(56, 58, 66),
+                background: Color::Rgb(250, 250, 250),
+                accent: Color::Rgb(228, 86, 73),
+                highlight: Color::Rgb(193, 132, 1),
+                primary: Color::Rgb(1, 132, 188),
+                emphasis: Color::Rgb(0, 0, 0),
+                monochrome: false,
+            },
+            ThemeName::Dark256 => Theme {
+                foreground: Color::Indexed(255),
+                background: Color::Indexed(236),
+                accent: Color::Indexed(203),
+                highlight: Color::Indexed(215),
+                primary: Color::Indexed(117),
+                emphasis: Color::Indexed(231),
+                monochrome: false,
+            },
+            ThemeName::Monochrome => Theme {
+                foreground: Color::Reset,
+                background: Color::Reset,
+                accent: Color::Reset,
+                highlight: Color::Reset,
+                primary: Color::Reset,
+                emphasis: Color::Reset,
+                monochrome: true,
+            },
+        }
+    }
+
+    /// The monochrome theme if the NO_COLOR environment variable is set to a non-empty
+    /// value (see https://no-color.org), and otherwise the named theme.
+    pub fn from_env(name: ThemeName) -> Theme {
+        match std::env::var_os("NO_COLOR") {
+            Some(value) if !value.is_empty() => Theme::new(ThemeName::Monochrome),
+            _ => Theme::new(name),
+        }
+    }
+
+    /// The style for plain text.
+    pub fn base(&self) -> Style {
+        Style::default().fg(self.foreground).bg(self.background)
+    }
+
+    /// The style for text in `colour`, which should be one of the theme's colours other
+    /// than the foreground colour. Use `base` for plain text.
+    pub fn fg(&self, colour: Color) -> Style {
+        if self.monochrome {
+            self.base().modifier(Modifier::BOLD)
+        } else {
+            self.base().fg(colour)
+        }
+    }
+
+    /// The style for the title of a panel with the given status.
+    pub fn status(&self, status: Status) -> Style {
+        match status {
+            Status::Active => self.fg(self.accent),
+            Status::Inactive => self.base(),
+        }
+    }
+
+    /// The style for a block of `colour`, such as a bar behind text.
+    pub fn fill(&self, colour: Color) -> Style {
+        if self.monochrome {
+            self.base().modifier(Modifier::REVERSED)
+        } else {
+            self.base().fg(self.background).bg(colour)
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::new(ThemeName::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_theme_name() {
+        let names: Vec<ThemeName> =
+            serde_json::from_str(r#"["dark", "light", "256", "monochrome"]"#).unwrap();
+        assert_eq!(
+            names,
+            vec![
+                ThemeName::Dark,
+                ThemeName::Light,
+                ThemeName::Dark256,
+                ThemeName::Monochrome
+            ]
+        );
+
+        let theme = Theme::new(ThemeName::Monochrome);
+        assert_eq!(
+            theme.fg(theme.accent),
+            theme.base().modifier(Modifier::BOLD)
+        );
+        assert_eq!(
+            theme.fill(theme.highlight),
+            theme.base().modifier(Modifier::REVERSED)
+        );
+    }
+
+    #[test]
+    fn test_active_panel_is_distinct() {
+        for name in &[
+            ThemeName::Dark,
+            ThemeName::Light,
+            ThemeName::Dark256,
+            ThemeName::Monochrome,
+        ] {
+            let theme = Theme::new(*name);
+            assert_ne!(theme.status(Status::Active), theme.status(Status::Inactive));
+            assert_eq!(theme.status(Status::Inactive), theme.base());
+        }
+    }
+}
@@ -0,0 +1,148 @@
+//! Converts an annotated log into the Chrome Trace Event format, which can be
+//! opened in Perfetto or chrome://tracing
+
+use crate::app::{App, Attribution};
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::io::Write;
+
+// Process ids used to group the tracks for lines and spans
+const LINES_PID: usize = 1;
+const SPANS_PID: usize = 2;
+
+fn micros(time: DateTime<Utc>, start: DateTime<Utc>) -> i64 {
+    (time - start).num_microseconds().unwrap_or(i64::MAX)
+}
+
+fn metadata(name: &str, pid: usize, tid: usize, value: &str) -> Value {
+    json!({
+        "name": name,
+        "ph": "M",
+        "pid": pid,
+        "tid": tid,
+        "args": { "name": value },
+    })
+}
+
+/// Returns one complete event per line, covering the gap attributed to that line, on a
+/// track per key. Each span becomes a complete event on a track per span rule and key.
+/// Events are named by the template of the line, or of the line starting the span.
+pub fn trace_events(app: &App) -> Vec<Value> {
+    let start = app.lines[0].timestamp;
+    let template = |line: usize| app.templates.names[app.templates.ids[line]].as_str();
+    let mut events = vec![
+        metadata("process_name", LINES_PID, 0, "Lines"),
+        metadata("process_name", SPANS_PID, 0, "Spans"),
+    ];
+
+    let mut line_tracks = BTreeMap::new();
+    for line in &app.lines {
+        let tid = line.key.map_or(0, |k| k + 1);
+        line_tracks
+            .entry(tid)
+            .or_insert_with(|| app.key_name(line.key).unwrap_or("(no key)"));
+
+        let begin = match app.attribution {
+            Attribution::Following => line.timestamp - line.elapsed,
+            Attribution::Preceding => line.timestamp,
+        };
+        events.push(json!({
+            "name": template(line.line_number),
+            "ph": "X",
+            "pid": LINES_PID,
+            "tid": tid,
+            "ts": micros(begin, start),
+            "dur": line.elapsed.num_microseconds().unwrap_or(i64::MAX),
+            "args": { "line": line.line_number, "text": line.line },
+        }));
+    }
+    for (tid, name) in line_tracks {
+        events.push(metadata("thread_name", LINES_PID, tid, name));
+    }
+
+    // Spans with the same rule and key are properly nested, so can share a track
+    let mut span_tracks = BTreeMap::new();
+    for span in &app.spans {
+        let next_tid = span_tracks.len() + 1;
+        let tid = *span_tracks
+            .entry((span.rule, span.key.clone()))
+            .or_insert(next_tid);
+        events.push(json!({
+            "name": template(span.start),
+            "ph": "X",
+            "pid": SPANS_PID,
+            "tid": tid,
+            "ts": micros(app.lines[span.start].timestamp, start),
+            "dur": span.duration.num_microseconds().unwrap_or(i64::MAX),
+            "args": { "start_line": span.start, "end_line": span.end, "key": span.key },
+        }));
+    }
+    for ((rule, key), tid) in span_tracks {
+        let name = match key {
+            Some(key) => format!("Rule {}: {}", rule, key),
+            None => format!("Rule {}", rule),
+        };
+        events.push(metadata("thread_name", SPANS_PID, tid, &name));
+    }
+
+    events
+}
+
+pub fn write_trace<W: Write + ?Sized>(app: &App, out: &mut W) -> Result<(), failure::Error> {
+    let trace = json!({
+        "traceEvents": trace_events(app),
+        "displayTimeUnit": "ms",
+    });
+    serde_json::to_writer(out, &trace)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::tests::log_lines;
+    use crate::app::Settings;
+    use crate::keys::KeyExtractor;
+    use crate::spans::SpanRule;
+    use regex::Regex;
+
+    #[test]
+    fn test_trace_events() {
+        let lines = log_lines(&[
+            (0, "[a] start 1"),
+            (1000, "[b] start 2"),
+            (1500, "[a] end 1"),
+        ]);
+        let settings = Settings {
+            key_extractor: Some(KeyExtractor::Regex(Regex::new(r"\[(\w+)\]").unwrap())),
+            span_rules: vec![
+                SpanRule::new(r"\[(?P<key>\w+)\] start", r"\[(?P<key>\w+)\] end").unwrap(),
+            ],
+            ..Settings::default()
+        };
+        let app = App::new(&lines, &settings);
+        let events = trace_events(&app);
+
+        let complete: Vec<_> = events.iter().filter(|e| e["ph"] == "X").collect();
+        assert_eq!(complete.len(), 4);
+
+        // The time between the two "a" lines is attributed to the second one
+        assert_eq!(complete[2]["name"], "<*> <*> [a] end <*>");
+        assert_eq!(complete[2]["tid"], 1);
+        assert_eq!(complete[2]["ts"], 0);
+        assert_eq!(complete[2]["dur"], 1_500_000);
+
+        assert_eq!(complete[3]["pid"], SPANS_PID);
+        assert_eq!(complete[3]["ts"], 0);
+        assert_eq!(complete[3]["dur"], 1_500_000);
+        assert_eq!(complete[3]["args"]["key"], "a");
+
+        let track_names: Vec<_> = events
+            .iter()
+            .filter(|e| e["name"] == "thread_name")
+            .map(|e| e["args"]["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(track_names, vec!["a", "b", "Rule 0: a"]);
+    }
+}